@@ -2,6 +2,11 @@ use clap::Parser;
 use std::path::PathBuf;
 
 use super::styles::clap_styles;
+use crate::config::Delivery;
+use crate::formatter::SvgTheme;
+use crate::git::{Forge, SigningFormat};
+use crate::readme::MarkerStyle;
+use crate::wakatime::Backend;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -11,10 +16,47 @@ pub struct Cli {
     #[arg(long, env = "INPUT_WAKATIME_API_KEY")]
     pub api_key: Option<String>,
 
+    /// WakaTime-compatible backend (wakatime, wakapi, hakatime)
+    #[arg(long, env = "INPUT_API_BACKEND", value_enum)]
+    pub api_backend: Option<Backend>,
+
+    /// API base URL (optional, defaults to the backend's public server)
+    #[arg(long, env = "INPUT_API_BASE_URL")]
+    pub api_base_url: Option<String>,
+
+    /// Number of attempts for WakaTime API requests
+    #[arg(long, env = "INPUT_RETRY_ATTEMPTS")]
+    pub retry_attempts: Option<u32>,
+
+    /// Initial retry delay in seconds, doubled after every attempt
+    #[arg(long, env = "INPUT_RETRY_DELAY")]
+    pub retry_delay: Option<u64>,
+
+    /// Render from a saved WakaTime stats response instead of calling the API (`{range}` is
+    /// replaced by the stats range)
+    #[arg(long, env = "INPUT_STATS_FILE")]
+    pub stats_file: Option<PathBuf>,
+
+    /// Save the stats responses to this path (`{range}` is replaced by the stats range)
+    #[arg(long, env = "INPUT_SAVE_STATS")]
+    pub save_stats: Option<PathBuf>,
+
     /// Path to README file
     #[arg(long, env = "INPUT_README_PATH", default_value = "README.md")]
     pub readme_path: PathBuf,
 
+    /// Comma-separated files to update instead of the README
+    #[arg(long, env = "INPUT_FILES")]
+    pub files: Option<String>,
+
+    /// Marker syntax (picked by file extension when empty)
+    #[arg(long, env = "INPUT_MARKERS", value_enum)]
+    pub markers: Option<MarkerStyle>,
+
+    /// Insert missing markers into the first file instead of failing: top, end, or a heading text
+    #[arg(long, env = "INPUT_INSERT_MARKERS")]
+    pub insert_markers: Option<String>,
+
     /// Section name in README to update
     #[arg(long, env = "INPUT_SECTION_NAME", default_value = "waka")]
     pub section_name: String,
@@ -27,6 +69,22 @@ pub struct Cli {
     #[arg(long, env = "INPUT_STATS_RANGE", default_value = "7")]
     pub stats_range: u32,
 
+    /// Print the rendered sections and a diff of the README without writing or committing
+    #[arg(long, env = "INPUT_DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Regexes, one per line, for text whose changes alone don't update the README (e.g. a date)
+    #[arg(long, env = "INPUT_VOLATILE_PATTERNS")]
+    pub volatile_patterns: Option<String>,
+
+    /// Ignore updates where every time moved by less than this many minutes (0 disables)
+    #[arg(long, env = "INPUT_MIN_CHANGE_MINUTES")]
+    pub min_change_minutes: Option<f64>,
+
+    /// Ignore updates where every time moved by less than this percent (0 disables)
+    #[arg(long, env = "INPUT_MIN_CHANGE_PERCENT")]
+    pub min_change_percent: Option<f64>,
+
     /// Whether to commit changes automatically
     #[arg(long, env = "INPUT_AUTO_COMMIT")]
     pub auto_commit: Option<bool>,
@@ -35,6 +93,18 @@ pub struct Cli {
     #[arg(long, env = "INPUT_AUTO_PUSH")]
     pub auto_push: Option<bool>,
 
+    /// How often to rebase and retry a push rejected as non-fast-forward
+    #[arg(long, env = "INPUT_PUSH_RETRIES")]
+    pub push_retries: Option<u32>,
+
+    /// How to deliver commits: push directly or open a pull request
+    #[arg(long, env = "INPUT_DELIVERY", value_enum)]
+    pub delivery: Option<Delivery>,
+
+    /// Branch used for pull request delivery
+    #[arg(long, env = "INPUT_PR_BRANCH")]
+    pub pr_branch: Option<String>,
+
     /// Git commit username
     #[arg(long, env = "INPUT_COMMIT_USERNAME")]
     pub git_user_name: Option<String>,
@@ -43,19 +113,83 @@ pub struct Cli {
     #[arg(long, env = "INPUT_COMMIT_EMAIL")]
     pub git_user_email: Option<String>,
 
+    /// Sign commits with an SSH or GPG key (not an action input, the action image has no gpg)
+    #[arg(long, env = "INPUT_SIGNING_FORMAT", value_enum)]
+    pub signing_format: Option<SigningFormat>,
+
+    /// Signing key: SSH private key path or content, GPG key ID or armored private key
+    #[arg(long, env = "INPUT_SIGNING_KEY", hide_env_values = true)]
+    pub signing_key: Option<String>,
+
+    /// Passphrase of the signing key
+    #[arg(long, env = "INPUT_SIGNING_KEY_PASSPHRASE", hide_env_values = true)]
+    pub signing_key_passphrase: Option<String>,
+
     /// GitHub token for pushing (optional, uses SSH if not provided)
     #[arg(long, env = "INPUT_GH_TOKEN")]
     pub github_token: Option<String>,
 
+    /// SSH deploy key for clone, fetch and push: private key path or content
+    #[arg(long, env = "INPUT_SSH_KEY", hide_env_values = true)]
+    pub ssh_key: Option<String>,
+
+    /// Passphrase of the SSH deploy key
+    #[arg(long, env = "INPUT_SSH_KEY_PASSPHRASE", hide_env_values = true)]
+    pub ssh_key_passphrase: Option<String>,
+
+    /// known_hosts path or content, SSH host keys are verified against it when set
+    #[arg(long, env = "INPUT_SSH_KNOWN_HOSTS")]
+    pub ssh_known_hosts: Option<String>,
+
+    /// Git hosting service (detected from the host when empty)
+    #[arg(long, env = "INPUT_FORGE", value_enum)]
+    pub forge: Option<Forge>,
+
+    /// Host `owner/repo` shorthands are cloned from (defaults to the forge's public host)
+    #[arg(long, env = "INPUT_GIT_HOST")]
+    pub git_host: Option<String>,
+
+    /// Branch to clone and push to (defaults to the remote default branch)
+    #[arg(long, env = "INPUT_BRANCH")]
+    pub branch: Option<String>,
+
     /// Git repository to clone (optional, for use without checkout action)
     #[arg(long, env = "INPUT_REPOSITORY")]
     pub repository: Option<String>,
 
-    /// Git commit message
+    /// Git commit message, supports {total_time}, {top_language}, {range}, {date} and {changed_sections}
     #[arg(
         long,
         env = "INPUT_COMMIT_MESSAGE",
         default_value = "📊 Updated WakaTime stats"
     )]
     pub commit_message: String,
+
+    /// Optional commit message body, supports the same placeholders plus {changes}
+    #[arg(long, env = "INPUT_COMMIT_BODY")]
+    pub commit_body: Option<String>,
+
+    /// Path to a custom template for the stats section
+    #[arg(long, env = "INPUT_TEMPLATE_PATH")]
+    pub template_path: Option<PathBuf>,
+
+    /// Render the stats as an SVG card at this path and link it from the section
+    #[arg(long, env = "INPUT_SVG_PATH")]
+    pub svg_path: Option<String>,
+
+    /// Theme of the SVG card
+    #[arg(long, env = "INPUT_SVG_THEME", value_enum)]
+    pub svg_theme: Option<SvgTheme>,
+
+    /// Append a "Last updated" line with the covered date range to every section
+    #[arg(long, env = "INPUT_LAST_UPDATED")]
+    pub last_updated: Option<bool>,
+
+    /// Timezone of the "Last updated" line, activity days and `{date}` (defaults to the user's)
+    #[arg(long, env = "INPUT_TIMEZONE")]
+    pub timezone: Option<String>,
+
+    /// strftime format of the "Last updated" time
+    #[arg(long, env = "INPUT_DATE_FORMAT")]
+    pub date_format: Option<String>,
 }
@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// How committed changes reach the remote
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    /// Push the current branch directly
+    #[default]
+    Push,
+    /// Push a dedicated branch and open or update a pull request
+    #[serde(alias = "pull-request")]
+    #[value(alias = "pull_request")]
+    PullRequest,
+}
@@ -1,23 +1,55 @@
 use crate::cli::Cli;
-use color_eyre::{eyre::{ContextCompat, WrapErr}, Result};
+use crate::formatter::{FooterFormatter, FormatOptions};
+use crate::readme::{ChangePolicy, MarkerAnchor, TargetFile};
+use crate::git::{CommitSigner, GitCredentials, SigningFormat, SshKey};
+use crate::wakatime::RetryPolicy;
+use chrono::format::{Item, StrftimeItems};
+use chrono_tz::Tz;
+use color_eyre::{eyre::{bail, eyre, ContextCompat, WrapErr}, Result};
 use git2::Repository;
-use std::{env, path::PathBuf};
+use regex::Regex;
+use std::{collections::HashSet, env, path::PathBuf, time::Duration};
 
-use super::toml::TomlConfig;
+use super::delivery::Delivery;
+use super::section::{Section, SectionKind, SvgCard};
+use super::toml::{StatsRange, TomlConfig};
 
 #[derive(Debug)]
 pub struct Config {
-    pub api_key: String,
-    pub readme_path: PathBuf,
-    pub section_name: String,
-    pub stats_range: u32,
+    pub api_key: Option<String>,
+    pub api_base_url: String,
+    pub retry: RetryPolicy,
+    pub stats_file: Option<PathBuf>,
+    pub save_stats: Option<PathBuf>,
+    /// Files the sections are written into, the README unless `files` is set
+    pub targets: Vec<TargetFile>,
+    /// Where to insert missing markers, `None` to fail instead
+    pub insert_markers: Option<MarkerAnchor>,
+    pub sections: Vec<Section>,
+    /// Append a "Last updated" line to every section
+    pub last_updated: bool,
+    /// Timezone of the "Last updated" line, `None` to use the WakaTime user's
+    pub timezone: Option<Tz>,
+    /// strftime format of the "Last updated" time
+    pub date_format: String,
+    pub dry_run: bool,
+    /// Which changes are worth writing and committing
+    pub change_policy: ChangePolicy,
     pub auto_commit: bool,
     pub auto_push: bool,
+    pub push_retries: u32,
+    pub delivery: Delivery,
+    pub pr_branch: String,
     pub git_user_name: String,
     pub git_user_email: String,
+    pub signer: Option<CommitSigner>,
     pub github_token: Option<String>,
+    pub credentials: GitCredentials,
+    pub git_host: String,
     pub repository: Option<String>,
+    pub branch: Option<String>,
     pub commit_message: String,
+    pub commit_body: Option<String>,
 }
 
 impl Config {
@@ -32,10 +64,48 @@ impl Config {
         };
 
         // Priority: CLI/ENV (INPUT_*) > TOML
-        let api_key = cli
-            .api_key
-            .or(toml_config.api_key)
-            .context("API key not provided. Please provide it via CLI argument, environment variable, or config file")?;
+        let stats_file = cli
+            .stats_file
+            .or_else(|| toml_config.stats_file.map(PathBuf::from))
+            .filter(|p| !p.as_os_str().is_empty());
+
+        let save_stats = cli
+            .save_stats
+            .or_else(|| toml_config.save_stats.map(PathBuf::from))
+            .filter(|p| !p.as_os_str().is_empty());
+
+        // The API key is optional when rendering offline from a stats file
+        let api_key = cli.api_key.or(toml_config.api_key).filter(|s| !s.is_empty());
+        if api_key.is_none() && stats_file.is_none() {
+            bail!("API key not provided. Please provide it via CLI argument, environment variable, or config file");
+        }
+
+        let api_backend = cli
+            .api_backend
+            .or(toml_config.api_backend)
+            .unwrap_or_default();
+
+        let api_base_url = cli
+            .api_base_url
+            .or(toml_config.api_base_url)
+            .filter(|s| !s.is_empty())
+            .or_else(|| api_backend.default_base_url().map(String::from))
+            .with_context(|| {
+                format!("API base URL not provided. The {:?} backend has no default server", api_backend)
+            })?;
+
+        let default_retry = RetryPolicy::default();
+        let retry = RetryPolicy {
+            max_attempts: cli
+                .retry_attempts
+                .or(toml_config.retry_attempts)
+                .unwrap_or(default_retry.max_attempts),
+            initial_delay: cli
+                .retry_delay
+                .or(toml_config.retry_delay)
+                .map(Duration::from_secs)
+                .unwrap_or(default_retry.initial_delay),
+        };
 
         let readme_path = if cli.readme_path.to_str() == Some("README.md") {
             toml_config
@@ -46,6 +116,40 @@ impl Config {
             cli.readme_path
         };
 
+        // A global marker style overrides the one picked by file extension
+        let markers = cli.markers.or(toml_config.markers);
+
+        // files replace readme_path, like [[sections]] replace the top-level section
+        let cli_files: Vec<PathBuf> = cli
+            .files
+            .iter()
+            .flat_map(|files| files.split(','))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .collect();
+
+        let targets = if !cli_files.is_empty() {
+            cli_files
+                .into_iter()
+                .map(|path| TargetFile::new(path, markers))
+                .collect()
+        } else if !toml_config.files.is_empty() {
+            toml_config
+                .files
+                .into_iter()
+                .map(|file| TargetFile::new(PathBuf::from(file.path), file.markers.or(markers)))
+                .collect()
+        } else {
+            vec![TargetFile::new(readme_path, markers)]
+        };
+
+        let insert_markers = cli
+            .insert_markers
+            .or(toml_config.insert_markers)
+            .filter(|s| !s.trim().is_empty())
+            .map(|anchor| MarkerAnchor::parse(&anchor));
+
         let section_name = if cli.section_name == "waka" {
             toml_config.section_name.unwrap_or(cli.section_name)
         } else {
@@ -58,6 +162,41 @@ impl Config {
             cli.stats_range
         };
 
+        let dry_run = cli.dry_run || toml_config.dry_run.unwrap_or(false);
+
+        // Patterns given on the CLI (one per line) replace the ones in TOML
+        let cli_patterns: Vec<String> = cli
+            .volatile_patterns
+            .iter()
+            .flat_map(|patterns| patterns.lines())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        let volatile_patterns = if cli_patterns.is_empty() {
+            toml_config.volatile_patterns
+        } else {
+            cli_patterns
+        };
+
+        let mut change_policy = ChangePolicy {
+            volatile: volatile_patterns
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern)
+                        .with_context(|| format!("Invalid volatile pattern: {}", pattern))
+                })
+                .collect::<Result<_>>()?,
+            min_minutes: cli
+                .min_change_minutes
+                .or(toml_config.min_change_minutes)
+                .unwrap_or(0.0),
+            min_percent: cli
+                .min_change_percent
+                .or(toml_config.min_change_percent)
+                .unwrap_or(0.0),
+        };
+
         let auto_commit = cli
             .auto_commit
             .or(toml_config.auto_commit)
@@ -68,6 +207,16 @@ impl Config {
             .or(toml_config.auto_push)
             .unwrap_or(false);
 
+        let push_retries = cli.push_retries.or(toml_config.push_retries).unwrap_or(3);
+
+        let delivery = cli.delivery.or(toml_config.delivery).unwrap_or_default();
+
+        let pr_branch = cli
+            .pr_branch
+            .or(toml_config.pr_branch)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "wakatime-stats".to_string());
+
         // Get git user name: CLI/ENV > TOML > Git Config > Default
         let git_user_name = cli
             .git_user_name
@@ -92,13 +241,157 @@ impl Config {
             })
             .unwrap_or_else(|| "github-actions[bot]@users.noreply.github.com".to_string());
 
-        let github_token = cli.github_token.or(toml_config.github_token);
+        // Commit signing is enabled by a key; the format defaults to SSH
+        let signing_key = cli
+            .signing_key
+            .or(toml_config.signing_key)
+            .filter(|s| !s.is_empty());
+        let signing_format = cli.signing_format.or(toml_config.signing_format);
+        let signing_key_passphrase = cli
+            .signing_key_passphrase
+            .or(toml_config.signing_key_passphrase)
+            .filter(|s| !s.is_empty());
+
+        let signer = signing_key
+            .map(|key| {
+                CommitSigner::new(
+                    signing_format.unwrap_or(SigningFormat::Ssh),
+                    key,
+                    signing_key_passphrase,
+                )
+            })
+            .transpose()?;
+
+        let github_token = cli
+            .github_token
+            .or(toml_config.github_token)
+            .filter(|s| !s.is_empty());
+
+        let ssh_key_passphrase = cli
+            .ssh_key_passphrase
+            .or(toml_config.ssh_key_passphrase)
+            .filter(|s| !s.is_empty());
+        let ssh_key = cli
+            .ssh_key
+            .or(toml_config.ssh_key)
+            .filter(|s| !s.is_empty())
+            .map(|key| SshKey {
+                key,
+                passphrase: ssh_key_passphrase,
+            });
+        let known_hosts = cli
+            .ssh_known_hosts
+            .or(toml_config.ssh_known_hosts)
+            .filter(|s| !s.is_empty());
+
+        // owner/repo shorthands use the forge's public host unless a host is configured
+        let forge = cli.forge.or(toml_config.forge);
+        let git_host = cli
+            .git_host
+            .or(toml_config.git_host)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| forge.unwrap_or_default().default_host().to_string());
+
+        let credentials = GitCredentials {
+            token: github_token.clone(),
+            ssh_key,
+            known_hosts,
+            forge,
+        };
 
         let commit_message = if cli.commit_message == "📊 Updated WakaTime stats" {
             toml_config.commit_message.unwrap_or(cli.commit_message)
         } else {
             cli.commit_message
         };
+        let commit_body = cli
+            .commit_body
+            .or(toml_config.commit_body)
+            .filter(|s| !s.trim().is_empty());
+
+        let template_path = cli
+            .template_path
+            .or_else(|| toml_config.template_path.map(PathBuf::from));
+
+        // [[sections]] replaces the single section built from the top-level options
+        let sections = if toml_config.sections.is_empty() {
+            vec![Section {
+                name: section_name,
+                kind: SectionKind::Stats,
+                range: StatsRange::Days(stats_range).to_api_range(),
+                days: Some(stats_range),
+                template_path,
+                all_time_header: toml_config.all_time_header.unwrap_or(false),
+                svg: SvgCard::from_options(
+                    cli.svg_path.or(toml_config.svg_path),
+                    cli.svg_theme.or(toml_config.svg_theme),
+                ),
+                format: FormatOptions::default(),
+            }]
+        } else {
+            toml_config
+                .sections
+                .into_iter()
+                .map(|section| Section::from_toml(section, stats_range))
+                .collect::<Result<_>>()?
+        };
+
+        // Each section owns its markers, two with the same name would overwrite each other
+        let mut names = HashSet::new();
+        if let Some(section) = sections.iter().find(|section| !names.insert(section.name.as_str())) {
+            bail!("Duplicate section name '{}', section names must be unique", section.name);
+        }
+
+        // A single saved response only holds one range
+        if let Some(stats_file) = &stats_file
+            && !stats_file.to_string_lossy().contains("{range}")
+        {
+            let mut ranges = sections
+                .iter()
+                .filter(|section| section.kind == SectionKind::Stats)
+                .map(|section| section.range.as_str())
+                .collect::<Vec<_>>();
+            ranges.sort_unstable();
+            ranges.dedup();
+            if ranges.len() > 1 {
+                bail!(
+                    "stats_file must contain {{range}} when sections use different ranges ({})",
+                    ranges.join(", ")
+                );
+            }
+        }
+
+        let last_updated = cli
+            .last_updated
+            .or(toml_config.last_updated)
+            .unwrap_or(false);
+
+        // The footer time changes on every run, which alone is no reason to commit
+        if last_updated {
+            let footer = Regex::new(FooterFormatter::VOLATILE_PATTERN).expect("footer pattern is valid");
+            change_policy.volatile.push(footer);
+        }
+
+        let timezone = cli
+            .timezone
+            .or(toml_config.timezone)
+            .filter(|s| !s.is_empty())
+            .map(|name| {
+                name.parse::<Tz>().map_err(|_| {
+                    eyre!("Unknown timezone: {} (expected a name like Europe/Berlin or UTC)", name)
+                })
+            })
+            .transpose()?;
+
+        // chrono panics when printing an invalid format, so reject it up front
+        let date_format = cli
+            .date_format
+            .or(toml_config.date_format)
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "%Y-%m-%d %H:%M %Z".to_string());
+        if StrftimeItems::new(&date_format).any(|item| item == Item::Error) {
+            bail!("Invalid date format: {}", date_format);
+        }
 
         // Get repository: CLI/ENV > TOML > GITHUB_REPOSITORY (GitHub Actions)
         let repository = cli
@@ -107,18 +400,37 @@ impl Config {
             .or_else(|| env::var("GITHUB_REPOSITORY").ok())
             .filter(|s| !s.is_empty());
 
+        let branch = cli.branch.or(toml_config.branch).filter(|s| !s.is_empty());
+
         Ok(Config {
             api_key,
-            readme_path,
-            section_name,
-            stats_range,
+            api_base_url,
+            retry,
+            stats_file,
+            save_stats,
+            targets,
+            insert_markers,
+            sections,
+            last_updated,
+            timezone,
+            date_format,
+            dry_run,
+            change_policy,
             auto_commit,
             auto_push,
+            push_retries,
+            delivery,
+            pr_branch,
             git_user_name,
             git_user_email,
+            signer,
             github_token,
+            credentials,
+            git_host,
             repository,
+            branch,
             commit_message,
+            commit_body,
         })
     }
 }
@@ -1,5 +1,9 @@
+mod delivery;
 mod loader;
+mod section;
 mod toml;
 
 // Re-export
+pub use delivery::Delivery;
 pub use loader::Config;
+pub use section::SectionKind;
@@ -0,0 +1,93 @@
+use color_eyre::{eyre::bail, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::formatter::{FormatOptions, SvgTheme};
+
+use super::toml::{SectionToml, StatsRange};
+
+/// What a section shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    /// Stats blocks for `range`
+    #[default]
+    Stats,
+    /// Only the total coding time since the account was created
+    AllTime,
+    /// Coding time per weekday over the last `days` days
+    Activity,
+}
+
+/// SVG card written next to the README instead of Markdown stats blocks
+#[derive(Debug)]
+pub struct SvgCard {
+    pub path: PathBuf,
+    pub theme: SvgTheme,
+}
+
+impl SvgCard {
+    pub(super) fn from_options(path: Option<String>, theme: Option<SvgTheme>) -> Option<Self> {
+        let path = path.filter(|p| !p.is_empty())?;
+
+        Some(SvgCard {
+            path: PathBuf::from(path),
+            theme: theme.unwrap_or_default(),
+        })
+    }
+}
+
+/// A README section to update, resolved from `[[sections]]` or the top-level options
+#[derive(Debug)]
+pub struct Section {
+    /// Marker name, as in `<!--START_SECTION:name-->`
+    pub name: String,
+    pub kind: SectionKind,
+    /// WakaTime range, e.g. `last_7_days` or `all_time`
+    pub range: String,
+    /// Range length, when the range was given as a number of days
+    pub days: Option<u32>,
+    pub template_path: Option<PathBuf>,
+    /// Prepend the all-time total to the stats blocks
+    pub all_time_header: bool,
+    pub svg: Option<SvgCard>,
+    pub format: FormatOptions,
+}
+
+impl Section {
+    pub(super) fn from_toml(section: SectionToml, default_range: u32) -> Result<Self> {
+        let defaults = FormatOptions::default();
+        let kind = section.kind.unwrap_or_default();
+        let range = section.range.unwrap_or(StatsRange::Days(default_range));
+
+        let days = match range {
+            StatsRange::Days(days) => Some(days),
+            StatsRange::Named(_) => None,
+        };
+
+        if kind == SectionKind::Activity && days.is_none_or(|days| days == 0) {
+            bail!(
+                "Section '{}': activity sections need the range as a number of days",
+                section.name
+            );
+        }
+
+        Ok(Section {
+            name: section.name,
+            kind,
+            range: range.to_api_range(),
+            days,
+            template_path: section.template_path.map(PathBuf::from),
+            all_time_header: section.all_time_header.unwrap_or(false),
+            svg: SvgCard::from_options(section.svg_path, section.svg_theme),
+            format: FormatOptions {
+                languages: section.languages.unwrap_or(defaults.languages),
+                editors: section.editors.unwrap_or(defaults.editors),
+                operating_systems: section
+                    .operating_systems
+                    .unwrap_or(defaults.operating_systems),
+                projects: section.projects.unwrap_or(defaults.projects),
+            },
+        })
+    }
+}
@@ -1,16 +1,102 @@
 use serde::Deserialize;
 
+use crate::formatter::SvgTheme;
+use crate::git::{Forge, SigningFormat};
+use crate::readme::MarkerStyle;
+use crate::wakatime::Backend;
+
+use super::delivery::Delivery;
+use super::section::SectionKind;
+
 #[derive(Debug, Deserialize, Default)]
 pub struct TomlConfig {
     pub api_key: Option<String>,
+    pub api_backend: Option<Backend>,
+    pub api_base_url: Option<String>,
+    pub retry_attempts: Option<u32>,
+    pub retry_delay: Option<u64>,
+    pub stats_file: Option<String>,
+    pub save_stats: Option<String>,
     pub readme_path: Option<String>,
     pub section_name: Option<String>,
     pub stats_range: Option<u32>,
+    pub dry_run: Option<bool>,
+    #[serde(default)]
+    pub volatile_patterns: Vec<String>,
+    pub min_change_minutes: Option<f64>,
+    pub min_change_percent: Option<f64>,
     pub auto_commit: Option<bool>,
     pub auto_push: Option<bool>,
+    pub push_retries: Option<u32>,
+    pub delivery: Option<Delivery>,
+    pub pr_branch: Option<String>,
     pub git_user_name: Option<String>,
     pub git_user_email: Option<String>,
+    pub signing_format: Option<SigningFormat>,
+    pub signing_key: Option<String>,
+    pub signing_key_passphrase: Option<String>,
     pub github_token: Option<String>,
+    pub ssh_key: Option<String>,
+    pub ssh_key_passphrase: Option<String>,
+    pub ssh_known_hosts: Option<String>,
+    pub forge: Option<Forge>,
+    pub git_host: Option<String>,
     pub repository: Option<String>,
+    pub branch: Option<String>,
     pub commit_message: Option<String>,
+    pub commit_body: Option<String>,
+    pub template_path: Option<String>,
+    pub all_time_header: Option<bool>,
+    pub svg_path: Option<String>,
+    pub svg_theme: Option<SvgTheme>,
+    pub last_updated: Option<bool>,
+    pub timezone: Option<String>,
+    pub date_format: Option<String>,
+    pub markers: Option<MarkerStyle>,
+    pub insert_markers: Option<String>,
+    #[serde(default)]
+    pub files: Vec<FileToml>,
+    #[serde(default)]
+    pub sections: Vec<SectionToml>,
+}
+
+/// One `[[files]]` entry
+#[derive(Debug, Deserialize)]
+pub struct FileToml {
+    pub path: String,
+    pub markers: Option<MarkerStyle>,
+}
+
+/// One `[[sections]]` entry
+#[derive(Debug, Deserialize)]
+pub struct SectionToml {
+    pub name: String,
+    pub kind: Option<SectionKind>,
+    pub range: Option<StatsRange>,
+    pub all_time_header: Option<bool>,
+    pub template_path: Option<String>,
+    pub svg_path: Option<String>,
+    pub svg_theme: Option<SvgTheme>,
+    pub languages: Option<usize>,
+    pub editors: Option<usize>,
+    pub operating_systems: Option<usize>,
+    pub projects: Option<usize>,
+}
+
+/// Either a number of days (`range = 30`) or a WakaTime range name (`range = "all_time"`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum StatsRange {
+    Days(u32),
+    Named(String),
+}
+
+impl StatsRange {
+    /// Range as used in the WakaTime stats endpoint
+    pub fn to_api_range(&self) -> String {
+        match self {
+            StatsRange::Days(days) => format!("last_{}_days", days),
+            StatsRange::Named(name) => name.clone(),
+        }
+    }
 }
@@ -0,0 +1,59 @@
+use chrono::{Datelike, Weekday};
+
+use crate::wakatime::summaries::DaySummary;
+
+use super::{format_duration, generate_bar};
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+pub struct ActivityFormatter;
+
+impl ActivityFormatter {
+    /// Render coding time per weekday (Mon–Sun), summed over all given days
+    pub fn format(days: &[DaySummary]) -> String {
+        let mut seconds = [0.0; 7];
+        for day in days {
+            seconds[day.range.date.weekday().num_days_from_monday() as usize] +=
+                day.grand_total.total_seconds;
+        }
+
+        let total: f64 = seconds.iter().sum();
+
+        let mut output = String::new();
+        output.push_str("**📅 Weekly Activity**:\n```text\n");
+        for (weekday, seconds) in WEEKDAYS.iter().zip(seconds) {
+            let percent = if total > 0.0 { seconds / total * 100.0 } else { 0.0 };
+            let bar = generate_bar(percent);
+            output.push_str(&format!(
+                "{:<15} {} {:>6} {}\n",
+                weekday_name(*weekday),
+                bar,
+                format!("{:.2}%", percent),
+                format_duration(seconds)
+            ));
+        }
+        output.push_str("```\n");
+
+        output
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
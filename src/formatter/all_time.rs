@@ -0,0 +1,33 @@
+use chrono::NaiveDate;
+
+use crate::wakatime::all_time::AllTimeData;
+
+use super::format_duration;
+
+pub struct AllTimeFormatter;
+
+impl AllTimeFormatter {
+    /// Render "X hrs since <date>", usable on its own or above the stats blocks
+    pub fn format(all_time: &AllTimeData) -> String {
+        let total_time = if all_time.text.is_empty() {
+            format_duration(all_time.total_seconds)
+        } else {
+            all_time.text.clone()
+        };
+
+        let since = all_time
+            .range
+            .as_ref()
+            .and_then(|range| range.start_date.as_deref())
+            .map(|date| {
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map(|d| d.format("%b %-d, %Y").to_string())
+                    .unwrap_or_else(|_| date.to_string())
+            });
+
+        match since {
+            Some(since) => format!("**⏳ All Time**: {} since {}\n\n", total_time, since),
+            None => format!("**⏳ All Time**: {}\n\n", total_time),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+
+use crate::readme::MarkerStyle;
+
+pub struct FooterFormatter;
+
+impl FooterFormatter {
+    /// Matches a whole footer line as written by `line`, so "Last updated:" in the user's own
+    /// text still counts as a change
+    pub const VOLATILE_PATTERN: &str = r"(?mR)^[_*/]Last updated: .*[_*/]$";
+
+    /// Render "Last updated: <time>", followed by the days the stats cover when known
+    pub fn format(now: DateTime<Tz>, date_format: &str, range: Option<(NaiveDate, NaiveDate)>) -> String {
+        let updated = now.format(date_format);
+
+        match range {
+            Some((start, end)) => format!(
+                "Last updated: {} · Stats from {} to {}",
+                updated,
+                start.format("%b %-d, %Y"),
+                end.format("%b %-d, %Y")
+            ),
+            None => format!("Last updated: {}", updated),
+        }
+    }
+
+    /// The footer as an italic line in the markup of files with `style` markers
+    pub fn line(footer: &str, style: MarkerStyle) -> String {
+        match style {
+            MarkerStyle::Html | MarkerStyle::AsciiDoc => format!("_{}_\n", footer),
+            // A trailing underscore would make it a link reference in reStructuredText
+            MarkerStyle::Rst => format!("*{}*\n", footer),
+            // Underscores underline in org-mode
+            MarkerStyle::Org => format!("/{}/\n", footer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn volatile_pattern_only_matches_footer_lines() {
+        let re = Regex::new(FooterFormatter::VOLATILE_PATTERN).unwrap();
+        let footer = "Last updated: 2026-10-18 · Stats from 2026-10-11 to 2026-10-17";
+
+        for style in [MarkerStyle::Html, MarkerStyle::AsciiDoc, MarkerStyle::Rst, MarkerStyle::Org] {
+            let line = FooterFormatter::line(footer, style);
+            let crlf = line.replace('\n', "\r\n");
+            assert_eq!(re.replace_all(&line, ""), "\n", "{:?}", style);
+            assert_eq!(re.replace_all(&crlf, ""), "\r\n", "{:?}", style);
+        }
+
+        assert!(!re.is_match("Last updated: yesterday\n"));
+        assert!(!re.is_match("Docs (_Last updated: 2024_)\n"));
+    }
+}
@@ -1,11 +1,42 @@
+mod activity;
+mod all_time;
+mod footer;
+mod svg;
+
 use crate::wakatime::stats::StatsData;
 
+// Re-export
+pub use activity::ActivityFormatter;
+pub use all_time::AllTimeFormatter;
+pub use footer::FooterFormatter;
+pub use svg::{SvgCardRenderer, SvgTheme};
+
 const BAR_LENGTH: usize = 25;
 
+/// Number of entries shown per block (0 hides the block)
+#[derive(Debug, Clone, Copy)]
+pub struct FormatOptions {
+    pub languages: usize,
+    pub editors: usize,
+    pub operating_systems: usize,
+    pub projects: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            languages: 5,
+            editors: 3,
+            operating_systems: 3,
+            projects: 5,
+        }
+    }
+}
+
 pub struct StatsFormatter;
 
 impl StatsFormatter {
-    pub fn format(stats: &StatsData) -> String {
+    pub fn format(stats: &StatsData, options: &FormatOptions) -> String {
         let mut output = String::new();
 
         // Total time
@@ -13,9 +44,9 @@ impl StatsFormatter {
         output.push_str(&format!("**🕐 Total Coding Time**: {}\n\n", total_time));
 
         // Programming languages
-        if !stats.languages.is_empty() {
+        if !stats.languages.is_empty() && options.languages > 0 {
             output.push_str("**💻 Programming Languages**:\n```text\n");
-            for lang in stats.languages.iter().take(5) {
+            for lang in stats.languages.iter().take(options.languages) {
                 let bar = generate_bar(lang.percent);
                 output.push_str(&format!(
                     "{:<15} {} {:>6} {}\n",
@@ -29,9 +60,9 @@ impl StatsFormatter {
         }
 
         // Editors
-        if !stats.editors.is_empty() {
+        if !stats.editors.is_empty() && options.editors > 0 {
             output.push_str("**🛠️ Editors**:\n```text\n");
-            for editor in stats.editors.iter().take(3) {
+            for editor in stats.editors.iter().take(options.editors) {
                 let bar = generate_bar(editor.percent);
                 output.push_str(&format!(
                     "{:<15} {} {:>6}\n",
@@ -44,9 +75,9 @@ impl StatsFormatter {
         }
 
         // Operating systems
-        if !stats.operating_systems.is_empty() {
+        if !stats.operating_systems.is_empty() && options.operating_systems > 0 {
             output.push_str("**💻 Operating Systems**:\n```text\n");
-            for os in stats.operating_systems.iter().take(3) {
+            for os in stats.operating_systems.iter().take(options.operating_systems) {
                 let bar = generate_bar(os.percent);
                 output.push_str(&format!(
                     "{:<15} {} {:>6}\n",
@@ -59,9 +90,9 @@ impl StatsFormatter {
         }
 
         // Projects
-        if !stats.projects.is_empty() {
+        if !stats.projects.is_empty() && options.projects > 0 {
             output.push_str("**📂 Projects**:\n```text\n");
-            for project in stats.projects.iter().take(5) {
+            for project in stats.projects.iter().take(options.projects) {
                 let bar = generate_bar(project.percent);
                 output.push_str(&format!(
                     "{:<30} {} {:>6}\n",
@@ -77,7 +108,7 @@ impl StatsFormatter {
     }
 }
 
-fn generate_bar(percent: f64) -> String {
+pub(crate) fn generate_bar(percent: f64) -> String {
     let filled = ((percent / 100.0) * BAR_LENGTH as f64).round() as usize;
     let filled = filled.min(BAR_LENGTH);
     let empty = BAR_LENGTH - filled;
@@ -85,7 +116,7 @@ fn generate_bar(percent: f64) -> String {
     format!("{}{}", "█".repeat(filled), "░".repeat(empty))
 }
 
-fn format_duration(seconds: f64) -> String {
+pub(crate) fn format_duration(seconds: f64) -> String {
     let hours = (seconds / 3600.0).floor() as u32;
     let minutes = ((seconds % 3600.0) / 60.0).floor() as u32;
 
@@ -0,0 +1,220 @@
+use clap::ValueEnum;
+use color_eyre::{eyre::WrapErr, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::readme::ChangePolicy;
+use crate::wakatime::stats::{StatItem, StatsData};
+
+use super::format_duration;
+
+// Numeric attribute values: positions, widths and heights
+static GEOMETRY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"="-?\d+(?:\.\d+)?""#).expect("geometry pattern is valid"));
+
+const CARD_WIDTH: f64 = 480.0;
+const PADDING: f64 = 25.0;
+const ROW_HEIGHT: f64 = 22.0;
+const LANGUAGE_BAR_HEIGHT: f64 = 8.0;
+
+/// Colors used for the language bar segments, cycled when there are more languages
+const LANGUAGE_COLORS: [&str; 8] = [
+    "#f1502f", "#3178c6", "#00add8", "#3572a5", "#b07219", "#f1e05a", "#89e051", "#c6538c",
+];
+
+/// Color scheme of the SVG card
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SvgTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+struct ThemeColors {
+    background: &'static str,
+    border: &'static str,
+    title: &'static str,
+    text: &'static str,
+    muted: &'static str,
+}
+
+impl SvgTheme {
+    fn colors(self) -> ThemeColors {
+        match self {
+            SvgTheme::Light => ThemeColors {
+                background: "#fffefe",
+                border: "#e4e2e2",
+                title: "#2f80ed",
+                text: "#434d58",
+                muted: "#8b949e",
+            },
+            SvgTheme::Dark => ThemeColors {
+                background: "#0d1117",
+                border: "#30363d",
+                title: "#58a6ff",
+                text: "#c9d1d9",
+                muted: "#8b949e",
+            },
+        }
+    }
+}
+
+pub struct SvgCardRenderer;
+
+impl SvgCardRenderer {
+    /// Render a card with the total time, a stacked language bar, languages and editors
+    pub fn render(stats: &StatsData, theme: SvgTheme, languages: usize, editors: usize) -> String {
+        let colors = theme.colors();
+        let languages: Vec<&StatItem> = stats.languages.iter().take(languages).collect();
+        let editors: Vec<&StatItem> = stats.editors.iter().take(editors).collect();
+
+        let mut body = String::new();
+        let mut y = PADDING + 18.0;
+
+        body.push_str(&format!(
+            r#"<text x="{PADDING}" y="{y}" class="title">WakaTime Stats</text>"#
+        ));
+        y += ROW_HEIGHT + 4.0;
+
+        body.push_str(&format!(
+            r#"<text x="{PADDING}" y="{y}" class="muted">Total</text><text x="{}" y="{y}" class="text" text-anchor="end">{}</text>"#,
+            CARD_WIDTH - PADDING,
+            escape(&format_duration(stats.total_seconds))
+        ));
+        y += ROW_HEIGHT;
+
+        if !languages.is_empty() {
+            body.push_str(&language_bar(&languages, y));
+            y += LANGUAGE_BAR_HEIGHT + ROW_HEIGHT;
+
+            for (index, lang) in languages.iter().enumerate() {
+                body.push_str(&format!(
+                    r#"<circle cx="{}" cy="{}" r="5" fill="{}"/>"#,
+                    PADDING + 5.0,
+                    y - 5.0,
+                    LANGUAGE_COLORS[index % LANGUAGE_COLORS.len()]
+                ));
+                body.push_str(&item_row(lang, PADDING + 16.0, y));
+                y += ROW_HEIGHT;
+            }
+        }
+
+        if !editors.is_empty() {
+            y += 6.0;
+            body.push_str(&format!(
+                r#"<text x="{PADDING}" y="{y}" class="subtitle">Editors</text>"#
+            ));
+            y += ROW_HEIGHT;
+
+            for editor in &editors {
+                body.push_str(&item_row(editor, PADDING, y));
+                y += ROW_HEIGHT;
+            }
+        }
+
+        let height = y - ROW_HEIGHT + PADDING + 6.0;
+
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{CARD_WIDTH}" height="{height}" viewBox="0 0 {CARD_WIDTH} {height}">
+<style>
+text {{ font-family: "Segoe UI", Ubuntu, "Helvetica Neue", Sans-Serif; font-size: 14px; }}
+.title {{ font-size: 18px; font-weight: 600; fill: {title}; }}
+.subtitle {{ font-weight: 600; fill: {title}; }}
+.text {{ fill: {text}; }}
+.muted {{ fill: {muted}; }}
+</style>
+<rect x="0.5" y="0.5" rx="4.5" width="{rect_width}" height="{rect_height}" fill="{background}" stroke="{border}"/>
+{body}
+</svg>
+"#,
+            title = colors.title,
+            text = colors.text,
+            muted = colors.muted,
+            background = colors.background,
+            border = colors.border,
+            rect_width = CARD_WIDTH - 1.0,
+            rect_height = height - 1.0,
+        )
+    }
+
+    /// Whether writing the card to `path` would change the file significantly
+    pub fn is_changed<P: AsRef<Path>>(path: P, svg: &str, policy: &ChangePolicy) -> bool {
+        let Ok(existing) = fs::read_to_string(path) else {
+            return true;
+        };
+        if existing == svg || policy.is_exact() {
+            return existing != svg;
+        }
+
+        // Positions and bar widths follow the numbers in the text, which the policy judges
+        policy.is_significant(
+            &GEOMETRY_RE.replace_all(&existing, "=\"\""),
+            &GEOMETRY_RE.replace_all(svg, "=\"\""),
+        )
+    }
+
+    /// Write the card to `path`, returns whether the file changed
+    pub fn write<P: AsRef<Path>>(path: P, svg: &str, policy: &ChangePolicy) -> Result<bool> {
+        let path = path.as_ref();
+
+        if !Self::is_changed(path, svg, policy) {
+            return Ok(false);
+        }
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        fs::write(path, svg).with_context(|| format!("Failed to write file: {}", path.display()))?;
+
+        Ok(true)
+    }
+}
+
+/// Stacked horizontal bar with one segment per language
+fn language_bar(languages: &[&StatItem], y: f64) -> String {
+    let width = CARD_WIDTH - 2.0 * PADDING;
+    let total: f64 = languages.iter().map(|lang| lang.percent).sum();
+    if total <= 0.0 {
+        return String::new();
+    }
+
+    let mut output = format!(
+        r#"<clipPath id="bar"><rect x="{PADDING}" y="{y}" width="{width}" height="{LANGUAGE_BAR_HEIGHT}" rx="4"/></clipPath><g clip-path="url(#bar)">"#
+    );
+
+    let mut x = PADDING;
+    for (index, lang) in languages.iter().enumerate() {
+        let segment = lang.percent / total * width;
+        output.push_str(&format!(
+            r#"<rect x="{x:.2}" y="{y}" width="{segment:.2}" height="{LANGUAGE_BAR_HEIGHT}" fill="{}"/>"#,
+            LANGUAGE_COLORS[index % LANGUAGE_COLORS.len()]
+        ));
+        x += segment;
+    }
+
+    output.push_str("</g>");
+    output
+}
+
+fn item_row(item: &StatItem, x: f64, y: f64) -> String {
+    format!(
+        r#"<text x="{x}" y="{y}" class="text">{}</text><text x="{}" y="{y}" class="muted" text-anchor="end">{} · {:.2}%</text>"#,
+        escape(&item.name),
+        CARD_WIDTH - PADDING,
+        escape(&item.text),
+        item.percent
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
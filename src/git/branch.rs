@@ -0,0 +1,43 @@
+use color_eyre::{eyre::eyre, Result};
+use git2::Repository;
+use log::info;
+
+pub struct GitBranch;
+
+impl GitBranch {
+    /// Name of the branch HEAD points to
+    pub fn current() -> Result<String> {
+        let repo = Repository::open(".")
+            .map_err(|e| eyre!("Failed to open Git repository: {}", e))?;
+
+        let head = repo
+            .head()
+            .map_err(|e| eyre!("Failed to get HEAD: {}", e))?;
+
+        head.shorthand()
+            .map(String::from)
+            .ok_or_else(|| eyre!("Failed to get branch name"))
+    }
+
+    /// Reset `name` to the current HEAD commit and switch to it
+    ///
+    /// Index and working tree are left untouched, so pending changes carry over.
+    pub fn switch_to_fresh(name: &str) -> Result<()> {
+        let repo = Repository::open(".")
+            .map_err(|e| eyre!("Failed to open Git repository: {}", e))?;
+
+        let head_commit = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| eyre!("Failed to get HEAD commit: {}", e))?;
+
+        repo.branch(name, &head_commit, true)
+            .map_err(|e| eyre!("Failed to create branch '{}': {}", name, e))?;
+
+        repo.set_head(&format!("refs/heads/{}", name))
+            .map_err(|e| eyre!("Failed to switch to branch '{}': {}", name, e))?;
+
+        info!("Switched to branch '{}'", name);
+        Ok(())
+    }
+}
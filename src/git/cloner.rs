@@ -1,22 +1,23 @@
 use color_eyre::{eyre::eyre, Result};
 use git2::{
-    build::RepoBuilder, Config as GitConfig, Cred, FetchOptions, RemoteCallbacks, Repository,
-    RepositoryInitOptions,
+    build::{CheckoutBuilder, RepoBuilder}, BranchType, Config as GitConfig, Direction,
+    FetchOptions, Remote, Repository, RepositoryInitOptions,
 };
 use log::info;
 use std::{env, fs};
 
+use super::{GitCredentials, RemoteUrl};
+
 pub struct GitCloner;
 
 impl GitCloner {
     /// Configure git safe.directory for current directory
     fn configure_safe_directory() {
-        if let Ok(current_dir) = env::current_dir() {
-            if let Some(current_dir_str) = current_dir.to_str() {
-                if let Ok(mut config) = GitConfig::open_default() {
-                    let _ = config.set_str("safe.directory", current_dir_str);
-                }
-            }
+        if let Ok(current_dir) = env::current_dir()
+            && let Some(current_dir_str) = current_dir.to_str()
+            && let Ok(mut config) = GitConfig::open_default()
+        {
+            let _ = config.set_str("safe.directory", current_dir_str);
         }
     }
 
@@ -34,11 +35,43 @@ impl GitCloner {
             .unwrap_or(false)
     }
 
+    /// Log what `ensure_repository` would do, without touching git (for dry runs)
+    pub fn log_planned(repository: Option<&str>, branch: Option<&str>) {
+        let Ok(repo) = Repository::open(".") else {
+            match repository.filter(|s| !s.is_empty()) {
+                Some(repository) => info!("Would clone {}", repository),
+                None => info!("Not in a git repository, a real run needs the repository parameter"),
+            }
+            return;
+        };
+
+        let current = repo
+            .head()
+            .ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(String::from));
+        if let Some(branch) = branch
+            && current.as_deref() != Some(branch)
+        {
+            info!("Would switch to branch '{}'", branch);
+        }
+    }
+
     /// Clone repository if needed (when not using checkout action)
-    pub fn ensure_repository(repository: Option<String>, token: Option<String>) -> Result<()> {
-        // If already in a git repo, nothing to do
+    ///
+    /// Checks out `branch`, or the remote's default branch when not set.
+    pub fn ensure_repository(
+        repository: Option<String>,
+        branch: Option<&str>,
+        git_host: &str,
+        credentials: &GitCredentials,
+    ) -> Result<()> {
+        // If already in a git repo, only make sure the configured branch is checked out
         if Self::is_git_repo() {
             info!("Already in a git repository");
+            if let Some(branch) = branch {
+                Self::switch_branch(branch, credentials)?;
+            }
             return Ok(());
         }
 
@@ -55,49 +88,42 @@ impl GitCloner {
         info!("Not in a git repository, cloning: {}", repo_url);
 
         // Build clone URL
-        let clone_url = if repo_url.starts_with("git://github.com/") {
-            // Convert git:// to https:// for token authentication
-            repo_url.replace("git://", "https://")
-        } else if repo_url.contains('/') && !repo_url.starts_with("http") {
-            // Convert owner/repo to https://github.com/owner/repo.git
-            format!("https://github.com/{}.git", repo_url)
-        } else if repo_url.starts_with("https://github.com/") {
-            if !repo_url.ends_with(".git") {
-                format!("{}.git", repo_url)
-            } else {
-                repo_url.clone()
+        let clone_url = match RemoteUrl::parse(&repo_url, git_host) {
+            // Tokens only work over HTTPS
+            Some(remote) if credentials.token.is_some() => remote.https_url(),
+            // Convert owner/repo to git@host:owner/repo.git for deploy keys
+            Some(remote) if credentials.ssh_key.is_some() && !repo_url.contains(':') => {
+                remote.ssh_url()
             }
-        } else {
-            repo_url.clone()
-        };
-
-        // Set up credentials callback
-        let mut callbacks = RemoteCallbacks::new();
-        let token_clone = token.clone();
-        callbacks.credentials(move |_url, _username_from_url, _allowed_types| {
-            if let Some(ref token) = token_clone {
-                Cred::userpass_plaintext("x-access-token", token)
-            } else {
-                Err(git2::Error::from_str("No authentication provided"))
+            // Convert owner/repo and git:// to https://
+            Some(remote) if !repo_url.contains(':') || repo_url.starts_with("git://") => {
+                remote.https_url()
             }
-        });
+            _ => repo_url.clone(),
+        };
 
         // If directory is not empty, use init + remote + fetch approach
         if !Self::is_directory_empty() {
             info!("Directory not empty, using init + fetch approach");
-            Self::init_and_fetch(&clone_url, token)?;
+            Self::init_and_fetch(&clone_url, branch, credentials)?;
         } else {
             info!("Directory is empty, using clone approach");
             // Configure fetch options
+            let provider = credentials.provider();
             let mut fetch_options = FetchOptions::new();
-            fetch_options.remote_callbacks(callbacks);
+            fetch_options.remote_callbacks(provider.remote_callbacks());
             fetch_options.depth(1); // Shallow clone
 
-            // Clone the repository
-            RepoBuilder::new()
-                .fetch_options(fetch_options)
+            // Clone the repository, libgit2 checks out the remote HEAD unless a branch is set
+            let mut builder = RepoBuilder::new();
+            builder.fetch_options(fetch_options);
+            if let Some(branch) = branch {
+                builder.branch(branch);
+            }
+            builder
                 .clone(&clone_url, std::path::Path::new("."))
                 .map_err(|e| eyre!("Failed to clone repository: {}", e))?;
+            provider.log_success();
         }
 
         info!("Repository cloned successfully");
@@ -105,7 +131,11 @@ impl GitCloner {
     }
 
     /// Initialize repo and fetch from remote (for non-empty directories)
-    fn init_and_fetch(clone_url: &str, token: Option<String>) -> Result<()> {
+    fn init_and_fetch(
+        clone_url: &str,
+        branch: Option<&str>,
+        credentials: &GitCredentials,
+    ) -> Result<()> {
         // Initialize repository (or open if already exists)
         let repo = Repository::init_opts(".", RepositoryInitOptions::new().initial_head("main"))
             .map_err(|e| eyre!("Failed to init repository: {}", e))?;
@@ -123,30 +153,15 @@ impl GitCloner {
             }
         };
 
-        // Set up credentials callback
-        let mut callbacks = RemoteCallbacks::new();
-        let token_clone = token;
-        callbacks.credentials(move |_url, _username_from_url, _allowed_types| {
-            if let Some(ref token) = token_clone {
-                Cred::userpass_plaintext("x-access-token", token)
-            } else {
-                Err(git2::Error::from_str("No authentication provided"))
-            }
-        });
-
-        // Configure fetch options
-        let mut fetch_options = FetchOptions::new();
-        fetch_options.remote_callbacks(callbacks);
-        fetch_options.depth(1); // Shallow fetch
+        // Use the configured branch, or ask the remote which one HEAD points to
+        let branch_name = match branch {
+            Some(branch) => branch.to_string(),
+            None => Self::default_branch(&mut remote, credentials)?,
+        };
 
         // Fetch from remote
-        remote
-            .fetch(&["main"], Some(&mut fetch_options), None)
-            .or_else(|_| {
-                // If main doesn't exist, try master
-                remote.fetch(&["master"], Some(&mut fetch_options), None)
-            })
-            .map_err(|e| eyre!("Failed to fetch from remote: {}", e))?;
+        Self::fetch_branch(&mut remote, &branch_name, credentials)
+            .map_err(|e| eyre!("Failed to fetch '{}' from remote: {}", branch_name, e))?;
 
         // Get the branch name
         let fetch_head = repo
@@ -162,6 +177,13 @@ impl GitCloner {
             .find_commit(fetch_commit.id())
             .map_err(|e| eyre!("Failed to find commit: {}", e))?;
 
+        // Point the local branch at it and switch to it
+        repo.branch(&branch_name, &commit, true)
+            .map_err(|e| eyre!("Failed to create branch '{}': {}", branch_name, e))?;
+
+        repo.set_head(&format!("refs/heads/{}", branch_name))
+            .map_err(|e| eyre!("Failed to switch to branch '{}': {}", branch_name, e))?;
+
         repo.reset(
             commit.as_object(),
             git2::ResetType::Hard,
@@ -169,16 +191,105 @@ impl GitCloner {
         )
         .map_err(|e| eyre!("Failed to reset to commit: {}", e))?;
 
-        // Set up branch tracking
-        let branch_name = repo
+        Ok(())
+    }
+
+    /// Check out `branch` in an existing repository, fetching it from origin when there is no
+    /// local branch yet
+    fn switch_branch(branch: &str, credentials: &GitCredentials) -> Result<()> {
+        let repo = Repository::open(".").map_err(|e| eyre!("Failed to open repository: {}", e))?;
+
+        let current = repo
             .head()
             .ok()
-            .and_then(|h| h.shorthand().map(String::from))
-            .unwrap_or_else(|| "main".to_string());
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(String::from));
+        if current.as_deref() == Some(branch) {
+            return Ok(());
+        }
 
-        repo.branch(&branch_name, &commit, true)
-            .ok();
+        info!(
+            "Checked out {}, switching to branch '{}'",
+            current.as_deref().unwrap_or("a detached HEAD"),
+            branch
+        );
+
+        let commit = match repo.find_branch(branch, BranchType::Local) {
+            Ok(local) => local
+                .get()
+                .peel_to_commit()
+                .map_err(|e| eyre!("Failed to resolve branch '{}': {}", branch, e))?,
+            Err(_) => {
+                let mut remote = repo.find_remote("origin").map_err(|e| {
+                    eyre!(
+                        "Branch '{}' does not exist and there is no origin to fetch it from: {}",
+                        branch,
+                        e
+                    )
+                })?;
+                Self::fetch_branch(&mut remote, branch, credentials)
+                    .map_err(|e| eyre!("Failed to fetch '{}' from remote: {}", branch, e))?;
+
+                let commit = repo
+                    .find_reference("FETCH_HEAD")
+                    .and_then(|fetch_head| fetch_head.peel_to_commit())
+                    .map_err(|e| eyre!("Branch '{}' was not found on the remote: {}", branch, e))?;
+                repo.branch(branch, &commit, false)
+                    .map_err(|e| eyre!("Failed to create branch '{}': {}", branch, e))?;
+                commit
+            }
+        };
+
+        // A safe checkout refuses to overwrite uncommitted changes instead of losing them
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| {
+                eyre!(
+                    "Failed to check out branch '{}', uncommitted changes conflict with it: {}",
+                    branch,
+                    e
+                )
+            })?;
+        repo.set_head(&format!("refs/heads/{}", branch))
+            .map_err(|e| eyre!("Failed to switch to branch '{}': {}", branch, e))?;
+
+        Ok(())
+    }
+
+    /// Branch the remote HEAD points to
+    fn default_branch(remote: &mut Remote, credentials: &GitCredentials) -> Result<String> {
+        let provider = credentials.provider();
+        let connection = remote
+            .connect_auth(Direction::Fetch, Some(provider.remote_callbacks()), None)
+            .map_err(|e| eyre!("Failed to connect to remote: {}", e))?;
+        provider.log_success();
+
+        let head = connection
+            .default_branch()
+            .map_err(|e| eyre!("Failed to detect the remote default branch: {}", e))?;
+
+        let branch = head
+            .as_str()
+            .and_then(|name| name.strip_prefix("refs/heads/"))
+            .map(String::from)
+            .ok_or_else(|| eyre!("Remote HEAD is not a branch"))?;
+
+        info!("Remote default branch is '{}'", branch);
+        Ok(branch)
+    }
+
+    /// Shallow fetch of a single branch
+    fn fetch_branch(
+        remote: &mut Remote,
+        branch: &str,
+        credentials: &GitCredentials,
+    ) -> Result<(), git2::Error> {
+        let provider = credentials.provider();
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(provider.remote_callbacks());
+        fetch_options.depth(1); // Shallow fetch
 
+        remote.fetch(&[branch], Some(&mut fetch_options), None)?;
+        provider.log_success();
         Ok(())
     }
 }
@@ -1,17 +1,21 @@
 use color_eyre::{eyre::eyre, Result};
-use git2::{Repository, Signature};
+use git2::{Oid, Repository, Signature};
 use log::info;
 use std::path::Path;
 
+use super::signer::CommitSigner;
+
 pub struct GitCommitter;
 
 impl GitCommitter {
+    /// Commit `file_paths` on the current branch, returns the new commit
     pub fn commit_changes<P: AsRef<Path>>(
-        file_path: P,
+        file_paths: &[P],
         commit_message: &str,
         user_name: &str,
         user_email: &str,
-    ) -> Result<()> {
+        signer: Option<&CommitSigner>,
+    ) -> Result<Oid> {
         info!("Committing changes to Git...");
 
         let repo = Repository::open(".")
@@ -22,10 +26,12 @@ impl GitCommitter {
             .index()
             .map_err(|e| eyre!("Failed to get Git index: {}", e))?;
 
-        // Add file to staging area
-        index
-            .add_path(file_path.as_ref())
-            .map_err(|e| eyre!("Failed to add file to staging area: {}", e))?;
+        // Add files to staging area
+        for file_path in file_paths {
+            index
+                .add_path(file_path.as_ref())
+                .map_err(|e| eyre!("Failed to add file to staging area: {}", e))?;
+        }
 
         index
             .write()
@@ -54,17 +60,49 @@ impl GitCommitter {
             .map_err(|e| eyre!("Failed to create signature: {}", e))?;
 
         // Create commit
-        repo.commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            commit_message,
-            &tree,
-            &[&parent_commit],
-        )
-        .map_err(|e| eyre!("Failed to create commit: {}", e))?;
+        let commit_oid = match signer {
+            Some(signer) => {
+                let buffer = repo
+                    .commit_create_buffer(
+                        &signature,
+                        &signature,
+                        commit_message,
+                        &tree,
+                        &[&parent_commit],
+                    )
+                    .map_err(|e| eyre!("Failed to create commit buffer: {}", e))?;
+
+                let content = buffer
+                    .as_str()
+                    .ok_or_else(|| eyre!("Commit buffer is not valid UTF-8"))?;
+
+                let commit_signature = signer.sign(content)?;
+
+                let commit_oid = repo
+                    .commit_signed(content, &commit_signature, Some("gpgsig"))
+                    .map_err(|e| eyre!("Failed to create signed commit: {}", e))?;
+
+                // commit_signed does not move any reference, so advance the branch ourselves
+                head.resolve()
+                    .and_then(|mut branch| branch.set_target(commit_oid, commit_message))
+                    .map_err(|e| eyre!("Failed to update HEAD: {}", e))?;
+
+                info!("Commit signed");
+                commit_oid
+            }
+            None => repo
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    commit_message,
+                    &tree,
+                    &[&parent_commit],
+                )
+                .map_err(|e| eyre!("Failed to create commit: {}", e))?,
+        };
 
         info!("Changes committed successfully!");
-        Ok(())
+        Ok(commit_oid)
     }
 }
@@ -0,0 +1,329 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use git2::{
+    CertificateCheckStatus, Config as GitConfig, Cred, CredentialType, Error, RemoteCallbacks,
+};
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use sha1::Sha1;
+use std::cell::Cell;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{Forge, RemoteUrl};
+
+/// SSH private key used for clone, fetch and push
+#[derive(Debug, Clone)]
+pub struct SshKey {
+    /// Path to the key file, or the key content itself
+    pub key: String,
+    pub passphrase: Option<String>,
+}
+
+/// Credentials shared by every remote operation (clone, fetch, push)
+#[derive(Debug, Clone, Default)]
+pub struct GitCredentials {
+    pub token: Option<String>,
+    pub ssh_key: Option<SshKey>,
+    /// known_hosts path or content; when set, SSH host keys must match an entry
+    pub known_hosts: Option<String>,
+    /// Forge of the remote, detected from its host when not set
+    pub forge: Option<Forge>,
+}
+
+/// Authentication methods, in the order they are tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    Token,
+    SshKey,
+    SshAgent,
+    CredentialHelper,
+}
+
+impl AuthMethod {
+    const ORDER: [AuthMethod; 4] = [
+        AuthMethod::Token,
+        AuthMethod::SshKey,
+        AuthMethod::SshAgent,
+        AuthMethod::CredentialHelper,
+    ];
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuthMethod::Token => "token",
+            AuthMethod::SshKey => "SSH key",
+            AuthMethod::SshAgent => "ssh-agent",
+            AuthMethod::CredentialHelper => "git credential helper",
+        };
+        f.write_str(name)
+    }
+}
+
+impl GitCredentials {
+    /// Forge serving `host`
+    pub fn forge_for(&self, host: &str) -> Forge {
+        self.forge.unwrap_or_else(|| Forge::detect(host))
+    }
+
+    /// Credential provider for a single remote operation
+    pub fn provider(&self) -> CredentialProvider {
+        CredentialProvider {
+            credentials: self.clone(),
+            last_method: Rc::new(Cell::new(None)),
+        }
+    }
+}
+
+/// Hands out credentials for one clone, fetch or push, moving on to the next
+/// method each time libgit2 asks again after a failed attempt
+pub struct CredentialProvider {
+    credentials: GitCredentials,
+    last_method: Rc<Cell<Option<AuthMethod>>>,
+}
+
+impl CredentialProvider {
+    /// Remote callbacks that authenticate with these credentials and verify SSH host keys
+    pub fn remote_callbacks(&self) -> RemoteCallbacks<'static> {
+        let mut callbacks = RemoteCallbacks::new();
+
+        let credentials = self.credentials.clone();
+        let last_method = Rc::clone(&self.last_method);
+        callbacks.credentials(move |url, username_from_url, allowed_types| {
+            let username = username_from_url.unwrap_or("git");
+
+            // SSH asks for the username on its own before any key
+            if allowed_types == CredentialType::USERNAME {
+                return Cred::username(username);
+            }
+
+            // Resume after the method libgit2 already tried
+            let next = last_method
+                .get()
+                .and_then(|method| AuthMethod::ORDER.iter().position(|m| *m == method))
+                .map_or(0, |index| index + 1);
+
+            for &method in &AuthMethod::ORDER[next..] {
+                let cred = match method {
+                    AuthMethod::Token => match &credentials.token {
+                        Some(token)
+                            if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) =>
+                        {
+                            let forge = RemoteUrl::parse(url, "")
+                                .map_or(Forge::default(), |remote| {
+                                    credentials.forge_for(&remote.host)
+                                });
+                            Cred::userpass_plaintext(forge.token_username(), token)
+                        }
+                        _ => continue,
+                    },
+                    AuthMethod::SshKey => match &credentials.ssh_key {
+                        Some(ssh_key) if allowed_types.contains(CredentialType::SSH_KEY) => {
+                            ssh_key_cred(username, ssh_key)
+                        }
+                        _ => continue,
+                    },
+                    AuthMethod::SshAgent if allowed_types.contains(CredentialType::SSH_KEY) => {
+                        Cred::ssh_key_from_agent(username)
+                    }
+                    AuthMethod::CredentialHelper
+                        if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) =>
+                    {
+                        GitConfig::open_default().and_then(|config| {
+                            Cred::credential_helper(&config, url, username_from_url)
+                        })
+                    }
+                    _ => continue,
+                };
+
+                match cred {
+                    Ok(cred) => {
+                        debug!("Trying {} authentication", method);
+                        last_method.set(Some(method));
+                        return Ok(cred);
+                    }
+                    Err(e) => debug!("{} authentication unavailable: {}", method, e),
+                }
+            }
+
+            Err(Error::from_str("No more authentication methods to try"))
+        });
+
+        if let Some(known_hosts) = self.credentials.known_hosts.clone() {
+            callbacks.certificate_check(move |cert, host| {
+                // Only SSH host keys are checked here, TLS is verified as usual
+                let Some(hostkey) = cert.as_hostkey().and_then(|h| h.hostkey()) else {
+                    return Ok(CertificateCheckStatus::CertificatePassthrough);
+                };
+
+                if is_known_host(&known_hosts, host, hostkey) {
+                    Ok(CertificateCheckStatus::CertificateOk)
+                } else {
+                    Err(Error::from_str(&format!(
+                        "Host key for {} is not in known_hosts",
+                        host
+                    )))
+                }
+            });
+        }
+
+        callbacks
+    }
+
+    /// Log which method authenticated the operation, once it succeeded
+    pub fn log_success(&self) {
+        if let Some(method) = self.last_method.get() {
+            info!("Authenticated with {}", method);
+        }
+    }
+}
+
+fn ssh_key_cred(username: &str, ssh_key: &SshKey) -> Result<Cred, Error> {
+    let passphrase = ssh_key.passphrase.as_deref();
+
+    if ssh_key.key.trim_start().starts_with("-----BEGIN") {
+        Cred::ssh_key_from_memory(username, None, &ssh_key.key, passphrase)
+    } else {
+        Cred::ssh_key(username, None, Path::new(&ssh_key.key), passphrase)
+    }
+}
+
+/// Whether `host` with `hostkey` matches an entry of a known_hosts file or content
+fn is_known_host(known_hosts: &str, host: &str, hostkey: &[u8]) -> bool {
+    // An existing file wins, paths may contain spaces too
+    let content = if Path::new(known_hosts).is_file() {
+        match fs::read_to_string(known_hosts) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to read known_hosts {}: {}", known_hosts, e);
+                return false;
+            }
+        }
+    } else if known_hosts.trim().contains(char::is_whitespace) {
+        // Content always contains "host keytype key" lines, a path does not
+        known_hosts.to_string()
+    } else {
+        warn!("known_hosts file {} does not exist", known_hosts);
+        return false;
+    };
+
+    matches_known_hosts(&content, host, hostkey)
+}
+
+/// Whether any line of known_hosts `content` lists `host` with `hostkey`
+fn matches_known_hosts(content: &str, host: &str, hostkey: &[u8]) -> bool {
+    content.lines().any(|line| {
+        let mut fields = line.split_whitespace();
+        let (Some(hosts), Some(_key_type), Some(key)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            return false;
+        };
+
+        if hosts.starts_with('#') {
+            return false;
+        }
+
+        // Entries are "host", "[host]:port" or hashed as "|1|salt|hash"
+        let matches_host = hosts.split(',').any(|pattern| {
+            pattern == host
+                || pattern
+                    .strip_prefix('[')
+                    .and_then(|p| p.split_once(']'))
+                    .is_some_and(|(name, _port)| name == host)
+                || is_hashed_host(pattern, host)
+        });
+
+        matches_host && STANDARD.decode(key).is_ok_and(|key| key == hostkey)
+    })
+}
+
+/// Whether a hashed known_hosts entry (`HashKnownHosts yes`) is `host`
+///
+/// The hash is HMAC-SHA1 of the host name, keyed with the salt.
+fn is_hashed_host(pattern: &str, host: &str) -> bool {
+    let Some((salt, hash)) = pattern.strip_prefix("|1|").and_then(|p| p.split_once('|')) else {
+        return false;
+    };
+    let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+        return false;
+    };
+
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(&salt) else {
+        return false;
+    };
+    mac.update(host.as_bytes());
+    mac.verify_slice(&hash).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+
+    fn hostkey() -> Vec<u8> {
+        STANDARD.decode(KEY).unwrap()
+    }
+
+    /// `|1|salt|hash` entry for `host`, as `ssh-keygen -H` writes it
+    fn hashed(host: &str) -> String {
+        let salt = b"0123456789abcdefghij";
+        let mut mac = Hmac::<Sha1>::new_from_slice(salt).unwrap();
+        mac.update(host.as_bytes());
+        let hash = mac.finalize().into_bytes();
+        format!("|1|{}|{}", STANDARD.encode(salt), STANDARD.encode(hash))
+    }
+
+    #[test]
+    fn matches_plain_and_bracketed_hosts() {
+        let content = format!(
+            "# comment\n\
+             github.com,140.82.121.4 ssh-ed25519 {KEY}\n\
+             [git.example.com]:2222 ssh-ed25519 {KEY}\n"
+        );
+
+        assert!(matches_known_hosts(&content, "github.com", &hostkey()));
+        assert!(matches_known_hosts(&content, "git.example.com", &hostkey()));
+        assert!(!matches_known_hosts(&content, "gitlab.com", &hostkey()));
+        assert!(!matches_known_hosts(&content, "github.com", b"other key"));
+    }
+
+    #[test]
+    fn matches_hashed_hosts() {
+        let content = format!("{} ssh-ed25519 {KEY}\n", hashed("github.com"));
+
+        assert!(matches_known_hosts(&content, "github.com", &hostkey()));
+        assert!(!matches_known_hosts(&content, "gitlab.com", &hostkey()));
+        assert!(!is_hashed_host("|1|not base64|x", "github.com"));
+
+        // Written by `ssh-keygen -H`
+        let entry = "|1|RdgSYQsWA14i0y0Te19Avx6Q884=|TpeWgSILbrXlHPaAdtxSS/Kb7Po=";
+        let content = format!("{entry} ssh-ed25519 {KEY}");
+        assert!(matches_known_hosts(&content, "github.com", &hostkey()));
+    }
+
+    #[test]
+    fn reads_known_hosts_from_a_path_with_spaces() {
+        let dir = std::env::temp_dir().join(format!("known hosts {}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("known_hosts");
+        fs::write(&path, format!("github.com ssh-ed25519 {KEY}\n")).unwrap();
+
+        let found = is_known_host(path.to_str().unwrap(), "github.com", &hostkey());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(found);
+    }
+
+    #[test]
+    fn takes_inline_content_and_rejects_missing_files() {
+        let content = format!("github.com ssh-ed25519 {KEY}");
+
+        assert!(is_known_host(&content, "github.com", &hostkey()));
+        assert!(!is_known_host("/nonexistent/known_hosts", "github.com", &hostkey()));
+    }
+}
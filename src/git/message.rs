@@ -0,0 +1,52 @@
+/// Values available to commit message templates
+#[derive(Debug, Default)]
+pub struct CommitContext {
+    /// Total coding time of the first stats section
+    pub total_time: String,
+    /// Most used language of the first stats section
+    pub top_language: String,
+    /// Range of the first stats section, e.g. `last_7_days`
+    pub range: String,
+    /// Date of the run (YYYY-MM-DD)
+    pub date: String,
+    /// Names of the README sections that changed
+    pub changed_sections: Vec<String>,
+    /// One line per changed section or file, listed by `{changes}`
+    pub changes: Vec<String>,
+}
+
+pub struct CommitMessage;
+
+impl CommitMessage {
+    /// Fill the subject and optional body templates
+    ///
+    /// Supported placeholders: `{total_time}`, `{top_language}`, `{range}`, `{date}`,
+    /// `{changed_sections}` and `{changes}` (a "- " bullet list). Unknown ones are kept.
+    pub fn render(subject: &str, body: Option<&str>, context: &CommitContext) -> String {
+        let subject = Self::fill(subject, context);
+        // The subject stays on one line, extra lines belong to the body
+        let subject = subject.lines().next().unwrap_or_default().trim().to_string();
+
+        match body.map(|body| Self::fill(body, context)) {
+            Some(body) if !body.trim().is_empty() => format!("{}\n\n{}\n", subject, body.trim()),
+            _ => subject,
+        }
+    }
+
+    fn fill(template: &str, context: &CommitContext) -> String {
+        let changes = context
+            .changes
+            .iter()
+            .map(|change| format!("- {}", change))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        template
+            .replace("{total_time}", &context.total_time)
+            .replace("{top_language}", &context.top_language)
+            .replace("{range}", &context.range)
+            .replace("{date}", &context.date)
+            .replace("{changed_sections}", &context.changed_sections.join(", "))
+            .replace("{changes}", &changes)
+    }
+}
@@ -1,8 +1,18 @@
+mod branch;
 mod cloner;
 mod committer;
+mod credentials;
+mod message;
 mod pusher;
+mod remote;
+mod signer;
 
 // Re-export
+pub use branch::GitBranch;
 pub use cloner::GitCloner;
 pub use committer::GitCommitter;
-pub use pusher::GitPusher;
+pub use credentials::{GitCredentials, SshKey};
+pub use message::{CommitContext, CommitMessage};
+pub use pusher::{GitPusher, PushResult};
+pub use remote::{Forge, RemoteUrl};
+pub use signer::{CommitSigner, SigningFormat};
@@ -1,12 +1,49 @@
-use color_eyre::{eyre::eyre, Result};
-use git2::{Cred, PushOptions, RemoteCallbacks, Repository};
+use color_eyre::{eyre::{bail, eyre}, Result};
+use git2::{
+    build::CheckoutBuilder, ErrorCode, FetchOptions, Oid, PushOptions, Remote, Repository,
+    ResetType,
+};
 use log::info;
+use std::cell::RefCell;
+
+use super::{GitCredentials, RemoteUrl};
+
+/// Outcome of a push attempt
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushResult {
+    Pushed,
+    /// The remote refused the update, usually because it has commits we don't
+    Rejected(String),
+}
 
 pub struct GitPusher;
 
 impl GitPusher {
-    pub fn push_changes(github_token: Option<String>) -> Result<()> {
-        info!("Pushing changes to remote...");
+    /// URL of the `origin` remote
+    pub fn origin_url() -> Result<String> {
+        let repo = Repository::open(".")
+            .map_err(|e| eyre!("Failed to open Git repository: {}", e))?;
+
+        let remote = repo
+            .find_remote("origin")
+            .map_err(|e| eyre!("Failed to find remote 'origin': {}", e))?;
+
+        remote
+            .url()
+            .map(String::from)
+            .ok_or_else(|| eyre!("Remote 'origin' has no valid URL"))
+    }
+
+    /// Push the current branch to `branch` on `origin`, overwriting it if `force` is set
+    ///
+    /// A push the remote rejects as non-fast-forward is reported as `PushResult::Rejected`
+    /// so the caller can rebase and retry.
+    pub fn push_changes(
+        credentials: &GitCredentials,
+        branch: &str,
+        force: bool,
+    ) -> Result<PushResult> {
+        info!("Pushing changes to '{}' on remote...", branch);
 
         let repo = Repository::open(".")
             .map_err(|e| eyre!("Failed to open Git repository: {}", e))?;
@@ -20,61 +57,154 @@ impl GitPusher {
             .shorthand()
             .ok_or_else(|| eyre!("Failed to get branch name"))?;
 
-        // Get remote
-        let remote_name = "origin";
-        let remote = repo
-            .find_remote(remote_name)
-            .map_err(|e| eyre!("Failed to find remote 'origin': {}", e))?;
-
-        // If token is provided, convert SSH URL to HTTPS for pushing
-        let original_url = remote.url().unwrap_or("");
-        let should_convert_url = github_token.is_some() && original_url.starts_with("git@github.com:");
-
-        // Create appropriate remote for pushing
-        let mut push_remote = if should_convert_url {
-            // Convert git@github.com:owner/repo.git -> https://github.com/owner/repo.git
-            let https_url = original_url
-                .replace("git@github.com:", "https://github.com/")
-                .to_string();
-
-            info!("Converting SSH URL to HTTPS for token authentication");
-            // Create an anonymous remote with HTTPS URL
-            repo.remote_anonymous(&https_url)
-                .map_err(|e| eyre!("Failed to create anonymous remote: {}", e))?
-        } else {
-            remote
-        };
+        let mut push_remote = Self::open_remote(&repo, credentials)?;
 
-        // Set up credentials callback
-        let mut callbacks = RemoteCallbacks::new();
-        let token_clone = github_token.clone();
-        callbacks.credentials(move |_url, username_from_url, _allowed_types| {
-            // Try GitHub token first if provided
-            if let Some(ref token) = token_clone {
-                return Cred::userpass_plaintext("x-access-token", token);
+        // Collect refs the remote refused to update
+        let rejection = RefCell::new(None);
+        let provider = credentials.provider();
+        let mut callbacks = provider.remote_callbacks();
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(status) = status {
+                *rejection.borrow_mut() = Some(format!("{}: {}", refname, status));
             }
-
-            // Try SSH key
-            if let Some(username) = username_from_url {
-                if let Ok(cred) = Cred::ssh_key_from_agent(username) {
-                    return Ok(cred);
-                }
-            }
-
-            // Try default SSH key
-            Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+            Ok(())
         });
 
         let mut push_options = PushOptions::new();
         push_options.remote_callbacks(callbacks);
 
         // Push to remote
-        let refspec = format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name);
-        push_remote
-            .push(&[&refspec], Some(&mut push_options))
-            .map_err(|e| eyre!("Failed to push changes: {}", e))?;
+        let refspec = format!(
+            "{}refs/heads/{}:refs/heads/{}",
+            if force { "+" } else { "" },
+            branch_name,
+            branch
+        );
+        match push_remote.push(&[&refspec], Some(&mut push_options)) {
+            Ok(()) => {}
+            Err(e) if e.code() == ErrorCode::NotFastForward => {
+                return Ok(PushResult::Rejected(e.message().to_string()));
+            }
+            Err(e) => return Err(eyre!("Failed to push changes: {}", e)),
+        }
+        provider.log_success();
+
+        drop(push_options);
+        if let Some(reason) = rejection.into_inner() {
+            return Ok(PushResult::Rejected(reason));
+        }
 
         info!("Changes pushed successfully to remote!");
-        Ok(())
+        Ok(PushResult::Pushed)
+    }
+
+    /// Fetch `branch` from `origin` and move the current branch onto it, dropping `update`
+    /// (the commit this run created, if any)
+    ///
+    /// Other local commits are replayed on top of the fetched branch and uncommitted changes
+    /// are kept, so only the caller's update has to be re-applied afterwards. Returns whether
+    /// any local commits were replayed, i.e. whether the branch is ahead of the remote.
+    pub fn rebase_onto_remote(
+        credentials: &GitCredentials,
+        branch: &str,
+        update: Option<Oid>,
+    ) -> Result<bool> {
+        info!("Fetching '{}' from remote...", branch);
+
+        let repo = Repository::open(".")
+            .map_err(|e| eyre!("Failed to open Git repository: {}", e))?;
+
+        let mut remote = Self::open_remote(&repo, credentials)?;
+
+        let provider = credentials.provider();
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(provider.remote_callbacks());
+
+        remote
+            .fetch(&[branch], Some(&mut fetch_options), None)
+            .map_err(|e| eyre!("Failed to fetch from remote: {}", e))?;
+        provider.log_success();
+
+        let fetch_head = repo
+            .find_reference("FETCH_HEAD")
+            .and_then(|r| r.peel_to_commit())
+            .map_err(|e| eyre!("Failed to find FETCH_HEAD: {}", e))?;
+
+        let head = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| eyre!("Failed to get HEAD: {}", e))?;
+
+        // Local commits the remote doesn't have, oldest first, without our update
+        let mut walk = repo.revwalk()?;
+        walk.push(head.id())?;
+        walk.hide(fetch_head.id())?;
+        let mut local = walk
+            .filter(|id| id.as_ref().ok() != update.as_ref())
+            .collect::<Result<Vec<_>, _>>()?;
+        local.reverse();
+        let replayed_any = !local.is_empty();
+
+        let mut tip = fetch_head;
+        for id in local {
+            let commit = repo.find_commit(id)?;
+            if commit.parent_count() > 1 {
+                bail!("Cannot replay local merge commit {} onto the remote branch", id);
+            }
+
+            let mut index = repo
+                .cherrypick_commit(&commit, &tip, 0, None)
+                .map_err(|e| eyre!("Failed to replay local commit {}: {}", id, e))?;
+            if index.has_conflicts() {
+                bail!("Local commit {} conflicts with the remote branch", id);
+            }
+
+            let tree = repo.find_tree(index.write_tree_to(&repo)?)?;
+            let replayed = repo.commit(
+                None,
+                &commit.author(),
+                &commit.committer(),
+                commit.message().unwrap_or_default(),
+                &tree,
+                &[&tip],
+            )?;
+            tip = repo.find_commit(replayed)?;
+        }
+
+        // A safe checkout only touches files that are unchanged since HEAD, then the branch
+        // follows without resetting anything else
+        repo.checkout_tree(tip.as_object(), Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| eyre!("Uncommitted changes conflict with the remote branch: {}", e))?;
+        repo.reset(tip.as_object(), ResetType::Soft, None)
+            .map_err(|e| eyre!("Failed to move to the remote branch: {}", e))?;
+
+        Ok(replayed_any)
+    }
+
+    /// `origin`, or an anonymous HTTPS remote for it when a token is used with an SSH URL
+    fn open_remote<'r>(repo: &'r Repository, credentials: &GitCredentials) -> Result<Remote<'r>> {
+        // Get remote
+        let remote_name = "origin";
+        let remote = repo
+            .find_remote(remote_name)
+            .map_err(|e| eyre!("Failed to find remote 'origin': {}", e))?;
+
+        // If token is provided, convert SSH URL to HTTPS
+        let ssh_remote = remote
+            .url()
+            .and_then(|url| RemoteUrl::parse(url, ""))
+            .filter(|parsed| credentials.token.is_some() && parsed.is_ssh());
+
+        let Some(ssh_remote) = ssh_remote else {
+            return Ok(remote);
+        };
+
+        // Convert git@host:owner/repo.git -> https://host/owner/repo.git
+        let https_url = ssh_remote.https_url();
+
+        info!("Converting SSH URL to HTTPS for token authentication");
+        // Create an anonymous remote with HTTPS URL
+        repo.remote_anonymous(&https_url)
+            .map_err(|e| eyre!("Failed to create anonymous remote: {}", e))
     }
 }
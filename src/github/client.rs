@@ -0,0 +1,61 @@
+use color_eyre::{eyre::eyre, Result};
+use reqwest::{Client, Method, RequestBuilder};
+use std::env;
+
+const GITHUB_API_BASE: &str = "https://api.github.com";
+
+pub struct GitHubClient {
+    pub(crate) client: Client,
+    pub(crate) token: String,
+    pub(crate) base_url: String,
+    /// Repository as `owner/repo`
+    pub(crate) repository: String,
+}
+
+impl GitHubClient {
+    /// Create a client for `owner/repo`, honoring `GITHUB_API_URL` (set by GitHub Actions, also for GHES)
+    pub fn new(token: String, repository: String) -> Self {
+        let base_url = env::var("GITHUB_API_URL")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| GITHUB_API_BASE.to_string());
+
+        Self {
+            client: Client::new(),
+            token,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            repository,
+        }
+    }
+
+    /// Owner part of `owner/repo`
+    pub(crate) fn owner(&self) -> &str {
+        self.repository.split('/').next().unwrap_or_default()
+    }
+
+    /// Build an authenticated request for a path below `/repos/{owner}/{repo}`
+    pub(crate) fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}/repos/{}{}", self.base_url, self.repository, path))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .header("User-Agent", concat!("wakatime-readme-updater/", env!("CARGO_PKG_VERSION")))
+    }
+}
+
+/// Extract `owner/repo` from `owner/repo`, an HTTPS or an SSH GitHub URL
+pub fn repository_slug(repository: &str) -> Result<String> {
+    let path = repository
+        .trim()
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .take(2)
+        .collect::<Vec<_>>();
+
+    match path.as_slice() {
+        [repo, owner] if !owner.is_empty() && !repo.is_empty() => Ok(format!("{}/{}", owner, repo)),
+        _ => Err(eyre!("Cannot determine owner/repo from: {}", repository)),
+    }
+}
@@ -0,0 +1,5 @@
+mod client;
+mod pulls;
+
+// Re-export public API
+pub use client::{repository_slug, GitHubClient};
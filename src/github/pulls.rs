@@ -0,0 +1,78 @@
+use color_eyre::{eyre::{bail, WrapErr}, Result};
+use log::info;
+use reqwest::{Method, Response};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::client::GitHubClient;
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: u64,
+    html_url: String,
+}
+
+impl GitHubClient {
+    /// Open a pull request from `head` into `base`, or update the one that is already open
+    ///
+    /// Returns the pull request URL.
+    pub async fn ensure_pull_request(
+        &self,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: &str,
+    ) -> Result<String> {
+        let response = self
+            .request(Method::GET, "/pulls")
+            .query(&[
+                ("head", format!("{}:{}", self.owner(), head)),
+                ("base", base.to_string()),
+                ("state", "open".to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| e.without_url())
+            .context("Failed to list pull requests")?;
+
+        let existing: Vec<PullRequest> = parse(response).await?;
+
+        let pull_request: PullRequest = if let Some(pull_request) = existing.into_iter().next() {
+            info!("Updating existing pull request #{}", pull_request.number);
+            let response = self
+                .request(Method::PATCH, &format!("/pulls/{}", pull_request.number))
+                .json(&json!({ "title": title, "body": body }))
+                .send()
+                .await
+                .map_err(|e| e.without_url())
+                .context("Failed to update pull request")?;
+            parse(response).await?
+        } else {
+            info!("Opening pull request from {} into {}", head, base);
+            let response = self
+                .request(Method::POST, "/pulls")
+                .json(&json!({ "title": title, "body": body, "head": head, "base": base }))
+                .send()
+                .await
+                .map_err(|e| e.without_url())
+                .context("Failed to open pull request")?;
+            parse(response).await?
+        };
+
+        Ok(pull_request.html_url)
+    }
+}
+
+async fn parse<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T> {
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        bail!("GitHub API returned error {}: {}", status, text);
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| e.without_url())
+        .context("Failed to parse GitHub API response")
+}
@@ -2,18 +2,30 @@ mod cli;
 mod config;
 mod formatter;
 mod git;
+mod github;
 mod readme;
 mod wakatime;
 
-use color_eyre::Result;
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use color_eyre::{eyre::{bail, ContextCompat}, Result};
 use clap::Parser;
 use cli::Cli;
-use config::Config;
-use formatter::StatsFormatter;
-use git::{GitCloner, GitCommitter, GitPusher};
-use log::info;
-use readme::{ReadmeUpdater, ReadmeValidator};
-use wakatime::WakaTimeClient;
+use config::{Config, Delivery, SectionKind};
+use formatter::{
+    format_duration, ActivityFormatter, AllTimeFormatter, FooterFormatter, StatsFormatter,
+    SvgCardRenderer,
+};
+use git::{
+    CommitContext, CommitMessage, Forge, GitBranch, GitCloner, GitCommitter, GitPusher, PushResult,
+    RemoteUrl,
+};
+use github::{repository_slug, GitHubClient};
+use log::{info, warn};
+use readme::{MarkerAnchor, ReadmeUpdater, ReadmeValidator, TargetFile, TemplateRenderer};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use wakatime::{all_time::AllTimeData, stats::StatsData, WakaTimeClient};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -31,45 +43,296 @@ async fn main() -> Result<()> {
     // Load configuration
     let config = Config::from_cli(cli)?;
 
-    // Ensure we're in a git repository (clone if needed)
-    GitCloner::ensure_repository(config.repository.clone(), config.github_token.clone())?;
+    // Ensure we're in a git repository (clone if needed), a dry run leaves git alone
+    if config.dry_run {
+        GitCloner::log_planned(config.repository.as_deref(), config.branch.as_deref());
+    } else {
+        GitCloner::ensure_repository(
+            config.repository.clone(),
+            config.branch.as_deref(),
+            &config.git_host,
+            &config.credentials,
+        )?;
+    }
 
     info!("Starting WakaTime stats update...");
 
-    // Ensure README has required section markers
-    ReadmeValidator::ensure_section_exists(&config.readme_path, &config.section_name)?;
+    // Ensure README has required section markers, missing ones go into the first file together
+    // with the rendered stats if enabled
+    for section in &config.sections {
+        match &config.insert_markers {
+            Some(_) if !ReadmeValidator::has_section(&config.targets, &section.name)? => {
+                let path = config.targets[0].path.display();
+                if config.dry_run {
+                    info!("Would insert markers for '{}' into {}", section.name, path);
+                } else {
+                    info!("Markers for '{}' will be inserted into {}", section.name, path);
+                }
+            }
+            _ => ReadmeValidator::ensure_section_exists(&config.targets, &section.name)?,
+        }
+    }
+
+    // Create WakaTime client (not available when rendering offline without an API key)
+    let client = config.api_key.clone().map(|api_key| {
+        WakaTimeClient::new(api_key, config.api_base_url.clone(), config.retry)
+    });
+    let client = || {
+        client
+            .as_ref()
+            .context("API key not provided. Only stats sections can be rendered from a stats file without it")
+    };
 
-    // Create WakaTime client
-    let client = WakaTimeClient::new(config.api_key.clone());
+    // Stats are fetched once per range, even if several sections share it
+    let mut stats_by_range: HashMap<String, StatsData> = HashMap::new();
+    let mut all_time: Option<AllTimeData> = None;
+    let mut rendered_sections: Vec<(String, String)> = Vec::new();
+    let mut svg_cards: Vec<(PathBuf, String)> = Vec::new();
 
-    // Fetch stats
-    info!("Fetching WakaTime data...");
-    let range = format!("last_{}_days", config.stats_range);
-    let stats = client.get_stats(&range).await?;
+    // Fetch stats first, they tell the user's timezone
+    for section in config.sections.iter().filter(|s| s.kind == SectionKind::Stats) {
+        if stats_by_range.contains_key(&section.range) {
+            continue;
+        }
+
+        // Fetch stats, or load them from a saved response
+        let (stats, body) = match &config.stats_file {
+            Some(stats_file) => {
+                let path = stats_file.to_string_lossy().replace("{range}", &section.range);
+                info!("Loading WakaTime data from {}...", path);
+                StatsData::load(&path)?
+            }
+            None => {
+                info!("Fetching WakaTime data for {}...", section.range);
+                client()?.get_stats(&section.range).await?
+            }
+        };
 
-    // Format stats
-    let formatted_stats = StatsFormatter::format(&stats);
+        if let Some(save_stats) = &config.save_stats {
+            let path = save_stats.to_string_lossy().replace("{range}", &section.range);
+            info!("Saving WakaTime data to {}...", path);
+            StatsData::save(&body, &path)?;
+        }
 
-    // Update README
-    info!("Updating README...");
-    let updated =
-        ReadmeUpdater::update(&config.readme_path, &config.section_name, &formatted_stats)?;
+        stats_by_range.insert(section.range.clone(), stats);
+    }
+
+    // Days are counted in the configured timezone, or the one WakaTime reports for the user
+    let timezone = config
+        .timezone
+        .or_else(|| {
+            stats_by_range
+                .values()
+                .find_map(|stats| stats.timezone.as_deref()?.parse::<Tz>().ok())
+        })
+        .unwrap_or(Tz::UTC);
+    let now = Utc::now().with_timezone(&timezone);
+
+    for section in &config.sections {
+        let mut formatted_stats = String::new();
+
+        // All-time total, standalone or as a header line
+        if section.kind == SectionKind::AllTime || section.all_time_header {
+            if all_time.is_none() {
+                info!("Fetching WakaTime all-time total...");
+                all_time = Some(client()?.get_all_time_since_today().await?);
+            }
+            if let Some(all_time) = &all_time {
+                formatted_stats.push_str(&AllTimeFormatter::format(all_time));
+            }
+        }
+
+        if section.kind == SectionKind::Stats {
+            let stats = &stats_by_range[&section.range];
+
+            // Format stats
+            if let Some(svg) = &section.svg {
+                let card = SvgCardRenderer::render(
+                    stats,
+                    svg.theme,
+                    section.format.languages,
+                    section.format.editors,
+                );
+                if config.dry_run && SvgCardRenderer::is_changed(&svg.path, &card, &config.change_policy) {
+                    println!("SVG card {} would be updated:\n{}", svg.path.display(), card);
+                }
+                svg_cards.push((svg.path.clone(), card));
+
+                // Link the card relative to the README (the first target file)
+                let src = relative_path(&svg.path, &config.targets[0].path)?;
+                formatted_stats.push_str(&format!(
+                    "<img src=\"{}\" alt=\"WakaTime stats\" />\n",
+                    src.display()
+                ));
+            } else {
+                formatted_stats.push_str(&match &section.template_path {
+                    Some(template_path) => TemplateRenderer::from_file(template_path)?.render(stats)?,
+                    None => StatsFormatter::format(stats, &section.format),
+                });
+            }
+        }
 
-    if updated {
-        info!("README updated successfully!");
+        if section.kind == SectionKind::Activity {
+            let days = section.days.unwrap_or(7).max(1);
+            let end = now.date_naive();
+            let start = end - Duration::days(i64::from(days) - 1);
 
+            info!("Fetching WakaTime summaries from {} to {}...", start, end);
+            let summaries = client()?.get_summaries(start, end).await?;
+            formatted_stats.push_str(&ActivityFormatter::format(&summaries));
+        }
+
+        rendered_sections.push((section.name.clone(), formatted_stats));
+    }
+
+    // Footer text per section, its markup depends on the file it goes into
+    let mut footers: HashMap<String, String> = HashMap::new();
+    if config.last_updated {
+        for section in &config.sections {
+            let range = match section.kind {
+                SectionKind::Stats => stats_by_range[&section.range].date_range(timezone),
+                _ => None,
+            };
+            let footer = FooterFormatter::format(now, &config.date_format, range);
+            footers.insert(section.name.clone(), footer);
+        }
+    }
+
+    if config.dry_run {
+        let sections = sections_for(&config.targets[0], &rendered_sections, &footers);
+        for (name, formatted_stats) in sections {
+            println!("Section '{}':\n{}", name, formatted_stats);
+        }
+    }
+
+    // Dry run: show what would change and stop before touching the README or git
+    if config.dry_run {
+        let missing = missing_markers(&config)?;
+        for (index, target) in config.targets.iter().enumerate() {
+            let insert = marker_insertion(&config, index, &missing);
+            let sections = sections_for(target, &rendered_sections, &footers);
+            match ReadmeUpdater::preview(target, &sections, &config.change_policy, insert)? {
+                Some(diff) => println!("{}", diff),
+                None => info!("No changes detected in {}.", target.path.display()),
+            }
+        }
+        return Ok(());
+    }
+
+    let changed_sections = find_changed_sections(&config, &rendered_sections, &footers)?;
+    let changed_files = write_outputs(&config, &rendered_sections, &footers, &svg_cards)?;
+
+    if !changed_files.is_empty() {
         // Auto-commit if enabled
         if config.auto_commit {
-            GitCommitter::commit_changes(
-                &config.readme_path,
-                &config.commit_message,
+            let message = commit_message(
+                &config,
+                now,
+                &stats_by_range,
+                all_time.as_ref(),
+                &changed_sections,
+                &changed_files,
+            );
+            let open_pull_request = config.auto_push && config.delivery == Delivery::PullRequest;
+            // Updates land on the configured branch, or the one that is checked out
+            let base_branch = match &config.branch {
+                Some(branch) => branch.clone(),
+                None => GitBranch::current()?,
+            };
+
+            // Pull requests get a dedicated branch, reset to the base on every run
+            if open_pull_request {
+                if config.pr_branch == base_branch {
+                    bail!("Pull request branch '{}' must differ from the base branch", base_branch);
+                }
+                GitBranch::switch_to_fresh(&config.pr_branch)?;
+            }
+
+            let update = GitCommitter::commit_changes(
+                &changed_files,
+                &message,
                 &config.git_user_name,
                 &config.git_user_email,
+                config.signer.as_ref(),
             )?;
 
             // Auto-push if enabled
-            if config.auto_push {
-                GitPusher::push_changes(config.github_token.clone())?;
+            if open_pull_request {
+                let token = config
+                    .github_token
+                    .clone()
+                    .context("A GitHub token is required to open pull requests")?;
+                let origin = GitPusher::origin_url()?;
+                if let Some(remote) = RemoteUrl::parse(&origin, &config.git_host)
+                    && config.credentials.forge_for(&remote.host) != Forge::GitHub
+                {
+                    bail!("Pull request delivery is only supported on GitHub");
+                }
+                let repository = match &config.repository {
+                    Some(repository) => repository_slug(repository)?,
+                    None => repository_slug(&origin)?,
+                };
+
+                if let PushResult::Rejected(reason) =
+                    GitPusher::push_changes(&config.credentials, &config.pr_branch, true)?
+                {
+                    bail!("Failed to push branch '{}': {}", config.pr_branch, reason);
+                }
+
+                let title = message.lines().next().unwrap_or_default();
+                let body = "Automated update of the WakaTime stats in the README.";
+                let url = GitHubClient::new(token, repository)
+                    .ensure_pull_request(&config.pr_branch, &base_branch, title, body)
+                    .await?;
+                info!("Pull request ready: {}", url);
+            } else if config.auto_push {
+                // Someone may have pushed since checkout: rebase by re-rendering onto their commit
+                let mut update = Some(update);
+                let mut retries = 0;
+                while let PushResult::Rejected(reason) =
+                    GitPusher::push_changes(&config.credentials, &base_branch, false)?
+                {
+                    if retries >= config.push_retries {
+                        bail!("Push rejected after {} retries: {}", retries, reason);
+                    }
+                    retries += 1;
+                    warn!(
+                        "Push rejected ({}), re-applying changes on top of the remote branch ({}/{})",
+                        reason, retries, config.push_retries
+                    );
+
+                    let ahead =
+                        GitPusher::rebase_onto_remote(&config.credentials, &base_branch, update)?;
+                    let changed_sections =
+                        find_changed_sections(&config, &rendered_sections, &footers)?;
+                    let changed_files =
+                        write_outputs(&config, &rendered_sections, &footers, &svg_cards)?;
+                    if changed_files.is_empty() {
+                        // Replayed local commits still have to be pushed
+                        if !ahead {
+                            info!("Remote branch already contains these changes");
+                            break;
+                        }
+                        update = None;
+                        continue;
+                    }
+
+                    let message = commit_message(
+                        &config,
+                        now,
+                        &stats_by_range,
+                        all_time.as_ref(),
+                        &changed_sections,
+                        &changed_files,
+                    );
+                    update = Some(GitCommitter::commit_changes(
+                        &changed_files,
+                        &message,
+                        &config.git_user_name,
+                        &config.git_user_email,
+                        config.signer.as_ref(),
+                    )?);
+                }
             }
         }
     } else {
@@ -78,3 +341,190 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// `path` as seen from the directory of `file`, stepping up with `..` where needed
+fn relative_path(path: &Path, file: &Path) -> Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let dir = std::path::absolute(file)?;
+    let dir = dir.parent().unwrap_or(&dir);
+
+    // Resolve `.` and `..` without touching the filesystem, the card may not exist yet
+    let normal = |path: &Path| {
+        let mut parts = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                other => parts.push(other.as_os_str().to_owned()),
+            }
+        }
+        parts
+    };
+    let (path, dir) = (normal(&path), normal(dir));
+
+    let common = path.iter().zip(&dir).take_while(|(a, b)| a == b).count();
+    let mut relative: PathBuf = dir[common..].iter().map(|_| Component::ParentDir).collect();
+    relative.extend(&path[common..]);
+    Ok(relative)
+}
+
+/// Sections no file has markers for, if they are to be inserted
+fn missing_markers(config: &Config) -> Result<Vec<String>> {
+    let mut missing = Vec::new();
+    if config.insert_markers.is_none() {
+        return Ok(missing);
+    }
+
+    for section in &config.sections {
+        if !ReadmeValidator::has_section(&config.targets, &section.name)? {
+            missing.push(section.name.clone());
+        }
+    }
+
+    Ok(missing)
+}
+
+/// Where and which `missing` markers to insert into the target at `index`, only the first file
+/// gets them
+fn marker_insertion<'a>(
+    config: &'a Config,
+    index: usize,
+    missing: &'a [String],
+) -> Option<(&'a MarkerAnchor, &'a [String])> {
+    config
+        .insert_markers
+        .as_ref()
+        .filter(|_| index == 0 && !missing.is_empty())
+        .map(|anchor| (anchor, missing))
+}
+
+/// Rendered sections with their footer, if any, in the markup of `target`
+fn sections_for(
+    target: &TargetFile,
+    rendered_sections: &[(String, String)],
+    footers: &HashMap<String, String>,
+) -> Vec<(String, String)> {
+    rendered_sections
+        .iter()
+        .map(|(name, formatted_stats)| match footers.get(name) {
+            Some(footer) => {
+                let footer = FooterFormatter::line(footer, target.markers);
+                (name.clone(), format!("{}\n\n{}", formatted_stats.trim_end(), footer))
+            }
+            None => (name.clone(), formatted_stats.clone()),
+        })
+        .collect()
+}
+
+/// Write the rendered README sections and SVG cards, returns the files that changed
+fn write_outputs(
+    config: &Config,
+    rendered_sections: &[(String, String)],
+    footers: &HashMap<String, String>,
+    svg_cards: &[(PathBuf, String)],
+) -> Result<Vec<PathBuf>> {
+    let mut changed_files = Vec::new();
+
+    for (path, card) in svg_cards {
+        if SvgCardRenderer::write(path, card, &config.change_policy)? {
+            info!("SVG card {} updated", path.display());
+            changed_files.push(path.clone());
+        }
+    }
+
+    // Update README and the other target files
+    let missing = missing_markers(config)?;
+    for (index, target) in config.targets.iter().enumerate() {
+        info!("Updating {}...", target.path.display());
+        let insert = marker_insertion(config, index, &missing);
+        let sections = sections_for(target, rendered_sections, footers);
+        if ReadmeUpdater::update(target, &sections, &config.change_policy, insert)? {
+            info!("{} updated successfully!", target.path.display());
+            changed_files.push(target.path.clone());
+        }
+    }
+
+    Ok(changed_files)
+}
+
+/// Names of the sections that change in any of the target files
+fn find_changed_sections(
+    config: &Config,
+    rendered_sections: &[(String, String)],
+    footers: &HashMap<String, String>,
+) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+
+    let missing = missing_markers(config)?;
+    for (index, target) in config.targets.iter().enumerate() {
+        let insert = marker_insertion(config, index, &missing);
+        let sections = sections_for(target, rendered_sections, footers);
+        let names = ReadmeUpdater::changed_sections(target, &sections, &config.change_policy, insert)?;
+        for name in names {
+            if !changed.contains(&name) {
+                changed.push(name);
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Fill the commit message templates from the fetched data and what changed
+fn commit_message(
+    config: &Config,
+    now: DateTime<Tz>,
+    stats_by_range: &HashMap<String, StatsData>,
+    all_time: Option<&AllTimeData>,
+    changed_sections: &[String],
+    changed_files: &[PathBuf],
+) -> String {
+    let mut context = CommitContext {
+        date: now.format("%Y-%m-%d").to_string(),
+        changed_sections: changed_sections.to_vec(),
+        ..Default::default()
+    };
+
+    // Totals come from the first stats section
+    if let Some(section) = config.sections.iter().find(|s| s.kind == SectionKind::Stats)
+        && let Some(stats) = stats_by_range.get(&section.range)
+    {
+        context.total_time = format_duration(stats.total_seconds);
+        context.top_language = stats
+            .languages
+            .first()
+            .map(|lang| lang.name.clone())
+            .unwrap_or_default();
+        context.range = section.range.clone();
+    }
+
+    for section in config.sections.iter().filter(|s| changed_sections.contains(&s.name)) {
+        let change = match (section.kind, stats_by_range.get(&section.range), all_time) {
+            (SectionKind::Stats, Some(stats), _) => format!(
+                "{}: {} ({})",
+                section.name,
+                format_duration(stats.total_seconds),
+                section.range
+            ),
+            (SectionKind::AllTime, _, Some(all_time)) => {
+                format!("{}: {} in total", section.name, all_time.text)
+            }
+            (SectionKind::Activity, _, _) => format!(
+                "{}: activity of the last {} days",
+                section.name,
+                section.days.unwrap_or(7)
+            ),
+            _ => section.name.clone(),
+        };
+        context.changes.push(change);
+    }
+
+    let is_target = |file: &PathBuf| config.targets.iter().any(|target| target.path == *file);
+    for file in changed_files.iter().filter(|file| !is_target(file)) {
+        context.changes.push(format!("{} updated", file.display()));
+    }
+
+    CommitMessage::render(&config.commit_message, config.commit_body.as_deref(), &context)
+}
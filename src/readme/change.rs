@@ -0,0 +1,203 @@
+use regex::{Captures, Match, Regex};
+use std::sync::LazyLock;
+
+// Durations (`2 hrs 5 mins`, `1,204 hrs`, `45 mins`), percentages and text bars
+static QUANTITY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(\d[\d,]*)\s*hrs?\b(?:\s+(\d+)\s*mins?\b)?|(\d+)\s*mins?\b|(\d+(?:\.\d+)?)\s*%|[█░]+",
+    )
+    .expect("quantity pattern is valid")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Quantity {
+    Minutes(f64),
+    Percent(f64),
+}
+
+/// Decides whether new section content is worth a commit
+///
+/// A change is noise when it is only in text matched by a volatile pattern, or when every
+/// number that moved stayed under one of the thresholds.
+#[derive(Debug, Default)]
+pub struct ChangePolicy {
+    /// Text ignored when comparing, e.g. a timestamp
+    pub volatile: Vec<Regex>,
+    /// Durations that moved by less than this many minutes are noise (0 disables)
+    pub min_minutes: f64,
+    /// Durations that moved by less than this percent of their previous value, and percentages
+    /// that moved by less than this many points, are noise (0 disables)
+    pub min_percent: f64,
+}
+
+impl ChangePolicy {
+    /// Whether any difference counts, i.e. no volatile patterns or thresholds are set
+    pub fn is_exact(&self) -> bool {
+        self.volatile.is_empty() && self.min_minutes <= 0.0 && self.min_percent <= 0.0
+    }
+
+    pub fn is_significant(&self, old: &str, new: &str) -> bool {
+        if old == new {
+            return false;
+        }
+        if self.is_exact() {
+            return true;
+        }
+
+        let (old, new) = (self.strip_volatile(old), self.strip_volatile(new));
+        if old == new {
+            return false;
+        }
+        if self.min_minutes <= 0.0 && self.min_percent <= 0.0 {
+            return true;
+        }
+
+        // Anything besides the numbers (names, order, headings) must be the same
+        let (old_text, old_values) = quantities(&old);
+        let (new_text, new_values) = quantities(&new);
+        if old_text != new_text || old_values.len() != new_values.len() {
+            return true;
+        }
+
+        old_values
+            .iter()
+            .zip(&new_values)
+            .any(|(old, new)| !self.is_noise(*old, *new))
+    }
+
+    /// Text without volatile matches, with whitespace collapsed so removed lines leave no trace
+    fn strip_volatile(&self, text: &str) -> String {
+        let text = self
+            .volatile
+            .iter()
+            .fold(text.to_string(), |text, re| re.replace_all(&text, "").into_owned());
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    fn is_noise(&self, old: Quantity, new: Quantity) -> bool {
+        // An unchanged value never counts, whichever threshold is set
+        if old == new {
+            return true;
+        }
+
+        match (old, new) {
+            (Quantity::Minutes(old), Quantity::Minutes(new)) => {
+                let delta = (new - old).abs();
+                (self.min_minutes > 0.0 && delta < self.min_minutes)
+                    || (self.min_percent > 0.0 && old > 0.0 && delta / old * 100.0 < self.min_percent)
+            }
+            // Shares follow the durations, so they only count against a percent threshold
+            (Quantity::Percent(old), Quantity::Percent(new)) => {
+                self.min_percent <= 0.0 || (new - old).abs() < self.min_percent
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Text with every quantity replaced by a placeholder (and whitespace collapsed, since padding
+/// depends on the width of the numbers), plus the quantities in order
+fn quantities(text: &str) -> (String, Vec<Quantity>) {
+    let number = |value: Option<Match>| {
+        value
+            .map(|m| m.as_str().replace(',', "").parse::<f64>().unwrap_or(0.0))
+            .unwrap_or(0.0)
+    };
+
+    let mut values = Vec::new();
+    let masked = QUANTITY_RE.replace_all(text, |caps: &Captures| {
+        if caps.get(1).is_some() {
+            values.push(Quantity::Minutes(number(caps.get(1)) * 60.0 + number(caps.get(2))));
+        } else if caps.get(3).is_some() {
+            values.push(Quantity::Minutes(number(caps.get(3))));
+        } else if caps.get(4).is_some() {
+            values.push(Quantity::Percent(number(caps.get(4))));
+        }
+        "\u{0}"
+    });
+
+    (masked.split_whitespace().collect::<Vec<_>>().join(" "), values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(volatile: &[&str], min_minutes: f64, min_percent: f64) -> ChangePolicy {
+        ChangePolicy {
+            volatile: volatile.iter().map(|re| Regex::new(re).unwrap()).collect(),
+            min_minutes,
+            min_percent,
+        }
+    }
+
+    #[test]
+    fn quantities_masks_durations_percentages_and_bars() {
+        let (text, values) = quantities(
+            "Rust   ████░░ 66.23% 1,204 hrs 5 mins\nGo 45 mins 2 hrs",
+        );
+
+        assert_eq!(text, "Rust \u{0} \u{0} \u{0} Go \u{0} \u{0}");
+        assert_eq!(
+            values,
+            vec![
+                Quantity::Percent(66.23),
+                Quantity::Minutes(1204.0 * 60.0 + 5.0),
+                Quantity::Minutes(45.0),
+                Quantity::Minutes(120.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn quantities_leaves_other_numbers_alone() {
+        let (text, values) = quantities("Top 3 projects in 2024");
+
+        assert_eq!(text, "Top 3 projects in 2024");
+        assert!(values.is_empty());
+    }
+
+    #[test]
+    fn exact_policy_counts_any_difference() {
+        let policy = ChangePolicy::default();
+
+        assert!(!policy.is_significant("Rust 2 hrs", "Rust 2 hrs"));
+        assert!(policy.is_significant("Rust 2 hrs", "Rust 2 hrs 1 min"));
+    }
+
+    #[test]
+    fn volatile_text_is_ignored() {
+        let policy = policy(&[r"Updated: .*"], 0.0, 0.0);
+
+        let old = "Rust 2 hrs\nUpdated: monday\n";
+
+        assert!(!policy.is_significant(old, "Rust 2 hrs\nUpdated: tuesday\n"));
+        assert!(!policy.is_significant("Rust 2 hrs\n", "Rust 2 hrs\nUpdated: tuesday\n"));
+        assert!(policy.is_significant(old, "Rust 3 hrs\nUpdated: tuesday\n"));
+    }
+
+    #[test]
+    fn small_duration_changes_are_noise() {
+        let policy = policy(&[], 10.0, 0.0);
+
+        assert!(!policy.is_significant("Rust 2 hrs 5 mins 50.00%", "Rust 2 hrs 9 mins 52.00%"));
+        assert!(policy.is_significant("Rust 2 hrs 5 mins", "Rust 2 hrs 15 mins"));
+    }
+
+    #[test]
+    fn percent_threshold_ignores_unchanged_zero_durations() {
+        let policy = policy(&[], 0.0, 5.0);
+
+        assert!(!policy.is_significant("Go 0 mins\nRust 100 mins", "Go 0 mins\nRust 102 mins"));
+        assert!(policy.is_significant("Go 0 mins\nRust 100 mins", "Go 3 mins\nRust 100 mins"));
+        assert!(policy.is_significant("Rust 100 mins 50%", "Rust 100 mins 60%"));
+    }
+
+    #[test]
+    fn changed_text_is_significant() {
+        let policy = policy(&[], 60.0, 50.0);
+
+        assert!(policy.is_significant("Rust 2 hrs", "Go 2 hrs"));
+        assert!(policy.is_significant("Rust 2 hrs", "Rust 2 hrs\nGo 1 min"));
+    }
+}
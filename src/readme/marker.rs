@@ -0,0 +1,88 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Comment syntax the section markers are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerStyle {
+    /// `<!--START_SECTION:name-->` (Markdown, HTML)
+    #[default]
+    Html,
+    /// `// START_SECTION:name`
+    #[value(name = "asciidoc")]
+    AsciiDoc,
+    /// `.. START_SECTION:name` (reStructuredText)
+    Rst,
+    /// `# START_SECTION:name` (org-mode)
+    Org,
+}
+
+impl MarkerStyle {
+    /// Style for a file extension, HTML comments for anything unknown
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("adoc" | "asciidoc" | "asc") => MarkerStyle::AsciiDoc,
+            Some("rst") => MarkerStyle::Rst,
+            Some("org") => MarkerStyle::Org,
+            _ => MarkerStyle::Html,
+        }
+    }
+
+    pub fn start(self, section_name: &str) -> String {
+        self.marker("START_SECTION", section_name)
+    }
+
+    pub fn end(self, section_name: &str) -> String {
+        self.marker("END_SECTION", section_name)
+    }
+
+    fn marker(self, kind: &str, section_name: &str) -> String {
+        match self {
+            MarkerStyle::Html => format!("<!--{}:{}-->", kind, section_name),
+            MarkerStyle::AsciiDoc => format!("// {}:{}", kind, section_name),
+            MarkerStyle::Rst => format!(".. {}:{}", kind, section_name),
+            MarkerStyle::Org => format!("# {}:{}", kind, section_name),
+        }
+    }
+}
+
+/// File the sections are written into
+#[derive(Debug, Clone)]
+pub struct TargetFile {
+    pub path: PathBuf,
+    pub markers: MarkerStyle,
+}
+
+impl TargetFile {
+    /// Target with markers from `markers`, or picked by the file extension
+    pub fn new(path: PathBuf, markers: Option<MarkerStyle>) -> Self {
+        let markers = markers.unwrap_or_else(|| MarkerStyle::from_path(&path));
+        Self { path, markers }
+    }
+}
+
+/// Where missing markers are inserted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerAnchor {
+    Top,
+    End,
+    /// After the heading with this text (heading characters like `#` are ignored)
+    AfterHeading(String),
+}
+
+impl MarkerAnchor {
+    /// `top`, `end`, or the text of a heading
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "top" => MarkerAnchor::Top,
+            "end" => MarkerAnchor::End,
+            heading => MarkerAnchor::AfterHeading(heading.to_string()),
+        }
+    }
+}
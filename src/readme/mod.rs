@@ -1,7 +1,14 @@
+mod change;
+mod marker;
+mod scanner;
+mod text;
 mod updater;
 mod validator;
-mod template;  // Reserved for future template system
+mod template;
 
 // Re-export
+pub use change::ChangePolicy;
+pub use marker::{MarkerAnchor, MarkerStyle, TargetFile};
+pub use template::TemplateRenderer;
 pub use updater::ReadmeUpdater;
 pub use validator::ReadmeValidator;
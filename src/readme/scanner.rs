@@ -0,0 +1,329 @@
+use color_eyre::{eyre::bail, Result};
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use super::marker::MarkerStyle;
+
+// Anywhere in a line: <!-- START_SECTION:name -->
+static HTML_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<!--\s*(START|END)_SECTION:\s*([^\s<>]+?)\s*-->").expect("marker pattern is valid")
+});
+
+// Whole comment lines
+static ASCIIDOC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[ \t]*//[ \t]*(START|END)_SECTION:[ \t]*(\S+)[ \t]*\r?$")
+        .expect("marker pattern is valid")
+});
+
+// Indented lines belong to literal blocks, so the comment has to start the line
+static RST_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\.\.[ \t]+(START|END)_SECTION:[ \t]*(\S+)[ \t]*\r?$")
+        .expect("marker pattern is valid")
+});
+
+static ORG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[ \t]*#[ \t]+(START|END)_SECTION:[ \t]*(\S+)[ \t]*\r?$")
+        .expect("marker pattern is valid")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerKind {
+    Start,
+    End,
+}
+
+#[derive(Debug)]
+struct Marker {
+    kind: MarkerKind,
+    name: String,
+    /// Byte range of the marker itself
+    range: Range<usize>,
+    /// 1-based line number
+    line: usize,
+}
+
+/// Section markers found in a file, outside of code blocks
+pub struct MarkerScanner {
+    markers: Vec<Marker>,
+}
+
+impl MarkerScanner {
+    pub fn scan(content: &str, style: MarkerStyle) -> Self {
+        let re: &Regex = match style {
+            MarkerStyle::Html => &HTML_RE,
+            MarkerStyle::AsciiDoc => &ASCIIDOC_RE,
+            MarkerStyle::Rst => &RST_RE,
+            MarkerStyle::Org => &ORG_RE,
+        };
+
+        let mut markers = Vec::new();
+        let mut block = CodeBlock::default();
+        let mut offset = 0;
+
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            let line_start = offset;
+            offset += line.len();
+
+            if block.skip(line, style) {
+                continue;
+            }
+
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            for captures in re.captures_iter(text) {
+                let (Some(whole), Some(kind), Some(name)) =
+                    (captures.get(0), captures.get(1), captures.get(2))
+                else {
+                    continue;
+                };
+
+                // Markers quoted in inline code are documentation, not markers
+                if style == MarkerStyle::Html && text[..whole.start()].matches('`').count() % 2 == 1 {
+                    continue;
+                }
+
+                // Keep the line ending outside of the marker
+                let start = line_start + whole.start();
+                let end = start + whole.as_str().trim_end_matches('\r').len();
+                markers.push(Marker {
+                    kind: if kind.as_str() == "START" { MarkerKind::Start } else { MarkerKind::End },
+                    name: name.as_str().to_string(),
+                    range: start..end,
+                    line: index + 1,
+                });
+            }
+        }
+
+        Self { markers }
+    }
+
+    /// Byte range between the section's start and end marker, `None` if it has no markers
+    ///
+    /// Duplicated, unbalanced or overlapping markers are errors.
+    pub fn section(&self, name: &str) -> Result<Option<Range<usize>>> {
+        let find = |kind: MarkerKind| {
+            self.markers
+                .iter()
+                .filter(|m| m.kind == kind && m.name == name)
+                .collect::<Vec<_>>()
+        };
+        let (starts, ends) = (find(MarkerKind::Start), find(MarkerKind::End));
+
+        let (start, end) = match (starts.as_slice(), ends.as_slice()) {
+            ([], []) => return Ok(None),
+            ([start], [end]) => (*start, *end),
+            ([first, second, ..], _) => bail!(
+                "Duplicate START_SECTION:{} on line {} (first one on line {})",
+                name, second.line, first.line
+            ),
+            (_, [first, second, ..]) => bail!(
+                "Duplicate END_SECTION:{} on line {} (first one on line {})",
+                name, second.line, first.line
+            ),
+            ([start], []) => bail!(
+                "START_SECTION:{} on line {} has no matching END_SECTION:{}",
+                name, start.line, name
+            ),
+            ([], [end]) => bail!(
+                "END_SECTION:{} on line {} has no matching START_SECTION:{}",
+                name, end.line, name
+            ),
+        };
+
+        if end.range.start < start.range.end {
+            bail!(
+                "END_SECTION:{} on line {} comes before START_SECTION:{} on line {}",
+                name, end.line, name, start.line
+            );
+        }
+
+        // Replacing the section would remove markers of another one
+        if let Some(inner) = self
+            .markers
+            .iter()
+            .find(|m| m.name != name && m.range.start > start.range.start && m.range.start < end.range.start)
+        {
+            bail!(
+                "Section '{}' (line {}) is inside section '{}' (lines {}-{})",
+                inner.name, inner.line, name, start.line, end.line
+            );
+        }
+
+        Ok(Some(start.range.end..end.range.start))
+    }
+}
+
+/// Tracks code blocks whose markers must be left alone
+#[derive(Default)]
+struct CodeBlock {
+    /// Line that closes the open block
+    closing: Option<String>,
+}
+
+impl CodeBlock {
+    /// Whether `line` opens, closes or is inside a code block
+    fn skip(&mut self, line: &str, style: MarkerStyle) -> bool {
+        let trimmed = line.trim();
+
+        if let Some(closing) = &self.closing {
+            if Self::closes(trimmed, closing, style) {
+                self.closing = None;
+            }
+            return true;
+        }
+
+        self.closing = Self::opening(trimmed, style);
+        self.closing.is_some()
+    }
+
+    fn opening(line: &str, style: MarkerStyle) -> Option<String> {
+        match style {
+            // ``` or ~~~ fences, closed by a fence of the same character and at least the same length
+            MarkerStyle::Html => {
+                let fence_char = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+                let fence: String = line.chars().take_while(|c| *c == fence_char).collect();
+                // The info string of a backtick fence can't contain backticks, so ```code```
+                // on one line is inline code rather than an opening fence
+                let info = &line[fence.len()..];
+                if fence_char == '`' && info.contains('`') {
+                    return None;
+                }
+                (fence.len() >= 3).then_some(fence)
+            }
+            // ----, ...., ++++ and //// delimited blocks
+            MarkerStyle::AsciiDoc => {
+                let first = line.chars().next().filter(|c| "-.+/".contains(*c))?;
+                (line.len() >= 4 && line.chars().all(|c| c == first)).then(|| line.to_string())
+            }
+            // #+BEGIN_SRC ... #+END_SRC
+            MarkerStyle::Org => {
+                let upper = line.to_ascii_uppercase();
+                let kind = upper.strip_prefix("#+BEGIN_")?.split_whitespace().next()?.to_string();
+                Some(format!("#+END_{}", kind))
+            }
+            MarkerStyle::Rst => None,
+        }
+    }
+
+    fn closes(line: &str, closing: &str, style: MarkerStyle) -> bool {
+        match style {
+            MarkerStyle::Html => {
+                line.len() >= closing.len() && line.chars().all(|c| closing.starts_with(c))
+            }
+            MarkerStyle::Org => line.to_ascii_uppercase() == closing,
+            MarkerStyle::AsciiDoc | MarkerStyle::Rst => line == closing,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(content: &str, style: MarkerStyle) -> Result<Option<Range<usize>>> {
+        MarkerScanner::scan(content, style).section("waka")
+    }
+
+    fn inner(content: &str, style: MarkerStyle) -> &str {
+        let range = section(content, style).unwrap().expect("section found");
+        &content[range]
+    }
+
+    #[test]
+    fn finds_markers_of_every_style() {
+        let cases = [
+            (MarkerStyle::Html, "a\n<!--START_SECTION:waka-->\nx\n<!-- END_SECTION:waka -->\n"),
+            (MarkerStyle::AsciiDoc, "a\n// START_SECTION:waka\nx\n// END_SECTION:waka\n"),
+            (MarkerStyle::Rst, "a\n.. START_SECTION:waka\nx\n.. END_SECTION:waka\n"),
+            (MarkerStyle::Org, "a\n# START_SECTION:waka\nx\n# END_SECTION:waka\n"),
+        ];
+
+        for (style, content) in cases {
+            assert_eq!(inner(content, style), "\nx\n", "{:?}", style);
+        }
+    }
+
+    #[test]
+    fn keeps_crlf_outside_of_markers() {
+        let content = "// START_SECTION:waka\r\nx\r\n// END_SECTION:waka\r\n";
+
+        assert_eq!(inner(content, MarkerStyle::AsciiDoc), "\r\nx\r\n");
+    }
+
+    #[test]
+    fn ignores_markers_in_fences_and_inline_code() {
+        let content = "\
+```md
+<!--START_SECTION:waka-->
+```
+~~~~
+<!--END_SECTION:waka-->
+~~~~
+Use `<!--START_SECTION:waka-->` to mark it.
+<!--START_SECTION:waka-->
+x
+<!--END_SECTION:waka-->
+";
+
+        assert_eq!(inner(content, MarkerStyle::Html), "\nx\n");
+    }
+
+    #[test]
+    fn one_line_backtick_span_is_not_a_fence() {
+        // Starts like a fence, but would swallow the rest of the file as one
+        let content = "```cargo run``` first\n<!--START_SECTION:waka-->\n<!--END_SECTION:waka-->\n";
+
+        assert_eq!(inner(content, MarkerStyle::Html), "\n");
+    }
+
+    #[test]
+    fn ignores_markers_in_delimited_blocks() {
+        let asciidoc = "----\n// START_SECTION:waka\n----\n\
+                        // START_SECTION:waka\n// END_SECTION:waka\n";
+        let org = "#+begin_src sh\n# START_SECTION:waka\n#+END_SRC\n\
+                   # START_SECTION:waka\n# END_SECTION:waka\n";
+
+        assert_eq!(inner(asciidoc, MarkerStyle::AsciiDoc), "\n");
+        assert_eq!(inner(org, MarkerStyle::Org), "\n");
+    }
+
+    #[test]
+    fn missing_section_is_none() {
+        assert_eq!(section("# Title\n", MarkerStyle::Html).unwrap(), None);
+    }
+
+    #[test]
+    fn reports_broken_markers_with_line_numbers() {
+        let cases = [
+            (
+                "<!--START_SECTION:waka-->\n",
+                "START_SECTION:waka on line 1 has no matching END_SECTION:waka",
+            ),
+            (
+                "a\n<!--END_SECTION:waka-->\n",
+                "END_SECTION:waka on line 2 has no matching START_SECTION:waka",
+            ),
+            (
+                "<!--START_SECTION:waka-->\n<!--START_SECTION:waka-->\n<!--END_SECTION:waka-->\n",
+                "Duplicate START_SECTION:waka on line 2 (first one on line 1)",
+            ),
+            (
+                "<!--START_SECTION:waka-->\n<!--END_SECTION:waka-->\n<!--END_SECTION:waka-->\n",
+                "Duplicate END_SECTION:waka on line 3 (first one on line 2)",
+            ),
+            (
+                "<!--END_SECTION:waka-->\n<!--START_SECTION:waka-->\n",
+                "END_SECTION:waka on line 1 comes before START_SECTION:waka on line 2",
+            ),
+            (
+                "<!--START_SECTION:waka-->\n<!--START_SECTION:code-->\n<!--END_SECTION:waka-->\n",
+                "Section 'code' (line 2) is inside section 'waka' (lines 1-3)",
+            ),
+        ];
+
+        for (content, message) in cases {
+            let error = section(content, MarkerStyle::Html).unwrap_err();
+            assert_eq!(error.to_string(), message);
+        }
+    }
+}
@@ -1,2 +1,182 @@
-// TODO: Template system for customizable README formats
-// This will allow users to define their own templates for different sections
+use color_eyre::{eyre::{bail, WrapErr}, Result};
+use regex::{Captures, Regex};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use crate::formatter::{format_duration, generate_bar};
+use crate::wakatime::stats::{StatItem, StatsData};
+
+/// `{{#each list limit=N}}...{{/each}}` blocks
+static LOOP_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{\{#each\s+(\w+)(?:\s+limit=(\d+))?\s*\}\}\n?(.*?)\{\{/each\}\}\n?")
+        .expect("loop pattern is valid")
+});
+
+/// `{{key}}`, `{{key:<N}}` and `{{key:>N}}` placeholders
+static PLACEHOLDER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*(\w+)(?::([<>])(\d+))?\s*\}\}").expect("placeholder pattern is valid")
+});
+
+/// User-defined layout for the stats section
+///
+/// Supported syntax:
+/// * `{{total_time}}`, `{{total_seconds}}`, `{{daily_average}}`, `{{best_day}}`,
+///   `{{best_day_time}}` - top-level values
+/// * `{{#each languages limit=5}}...{{/each}}` - loop over `languages`, `editors`,
+///   `operating_systems`, `projects`, `categories`, `machines` or `dependencies`
+///   (the `limit` is optional)
+/// * `{{name}}`, `{{text}}`, `{{percent}}`, `{{bar}}`, `{{index}}`, `{{digital}}`, `{{hours}}`,
+///   `{{minutes}}` - item values inside a loop
+/// * `{{name:<15}}`, `{{percent:>6}}` - left/right padding to a fixed width
+///
+/// A newline directly after an `each` tag is dropped, so loops can sit on their own lines.
+pub struct TemplateRenderer {
+    source: String,
+}
+
+impl TemplateRenderer {
+    pub fn new(source: String) -> Self {
+        Self { source }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read template: {}", path.display()))?;
+
+        Ok(Self::new(source))
+    }
+
+    pub fn render(&self, stats: &StatsData) -> Result<String> {
+        let mut output = String::new();
+        let mut last = 0;
+
+        for caps in LOOP_RE.captures_iter(&self.source) {
+            let whole = caps.get(0).unwrap();
+            output.push_str(&render_values(&self.source[last..whole.start()], |key| {
+                top_level_value(stats, key)
+            })?);
+
+            let items = list_by_name(stats, &caps[1])?;
+            let limit = match caps.get(2) {
+                Some(m) => m.as_str().parse().context("Invalid loop limit")?,
+                None => items.len(),
+            };
+
+            for (index, item) in items.iter().take(limit).enumerate() {
+                output.push_str(&render_values(&caps[3], |key| item_value(item, index, key))?);
+            }
+
+            last = whole.end();
+        }
+
+        output.push_str(&render_values(&self.source[last..], |key| {
+            top_level_value(stats, key)
+        })?);
+
+        if output.contains("{{#each") || output.contains("{{/each}}") {
+            bail!("Unbalanced {{{{#each}}}} / {{{{/each}}}} tags in template");
+        }
+
+        Ok(output)
+    }
+}
+
+/// Replace every `{{key}}` / `{{key:<N}}` placeholder using the given lookup
+fn render_values<F>(text: &str, lookup: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::new();
+    let mut last = 0;
+
+    for caps in PLACEHOLDER_RE.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        output.push_str(&text[last..whole.start()]);
+        output.push_str(&pad_value(&caps, &lookup)?);
+        last = whole.end();
+    }
+
+    output.push_str(&text[last..]);
+    Ok(output)
+}
+
+fn pad_value<F>(caps: &Captures, lookup: &F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let key = &caps[1];
+    let Some(value) = lookup(key) else {
+        bail!("Unknown template placeholder: {}", key);
+    };
+
+    let width: usize = match caps.get(3) {
+        Some(m) => m.as_str().parse().context("Invalid placeholder width")?,
+        None => return Ok(value),
+    };
+
+    Ok(match caps.get(2).map(|m| m.as_str()) {
+        Some(">") => format!("{:>width$}", value, width = width),
+        _ => format!("{:<width$}", value, width = width),
+    })
+}
+
+fn top_level_value(stats: &StatsData, key: &str) -> Option<String> {
+    match key {
+        "total_time" => Some(format_duration(stats.total_seconds)),
+        "total_seconds" => Some(format!("{:.0}", stats.total_seconds)),
+        "daily_average" => Some(
+            stats
+                .human_readable_daily_average
+                .clone()
+                .unwrap_or_else(|| format_duration(stats.daily_average.unwrap_or_default())),
+        ),
+        // Empty on backends that don't report a best day
+        "best_day" => Some(
+            stats
+                .best_day
+                .as_ref()
+                .and_then(|day| day.date)
+                .map(|date| date.format("%b %-d, %Y").to_string())
+                .unwrap_or_default(),
+        ),
+        "best_day_time" => Some(
+            stats
+                .best_day
+                .as_ref()
+                .map(|day| format_duration(day.total_seconds))
+                .unwrap_or_default(),
+        ),
+        _ => None,
+    }
+}
+
+fn item_value(item: &StatItem, index: usize, key: &str) -> Option<String> {
+    match key {
+        "name" => Some(item.name.clone()),
+        "text" => Some(item.text.clone()),
+        "percent" => Some(format!("{:.2}", item.percent)),
+        "bar" => Some(generate_bar(item.percent)),
+        "total_seconds" => Some(format!("{:.0}", item.total_seconds)),
+        "index" => Some((index + 1).to_string()),
+        "digital" => Some(item.digital.clone()),
+        "hours" => Some(item.hours.to_string()),
+        "minutes" => Some(item.minutes.to_string()),
+        _ => None,
+    }
+}
+
+fn list_by_name<'a>(stats: &'a StatsData, name: &str) -> Result<&'a [StatItem]> {
+    match name {
+        "languages" => Ok(&stats.languages),
+        "editors" => Ok(&stats.editors),
+        "operating_systems" => Ok(&stats.operating_systems),
+        "projects" => Ok(&stats.projects),
+        "categories" => Ok(&stats.categories),
+        "machines" => Ok(&stats.machines),
+        "dependencies" => Ok(&stats.dependencies),
+        _ => bail!("Unknown template list: {}", name),
+    }
+}
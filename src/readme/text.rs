@@ -0,0 +1,167 @@
+use color_eyre::{eyre::WrapErr, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+const BOM: char = '\u{feff}';
+
+/// Text file read with its BOM and CRLF line endings split off, so edits only deal with `\n`
+pub struct TextFile {
+    /// Content without BOM, with `\n` line endings
+    pub content: String,
+    bom: bool,
+    crlf: bool,
+}
+
+impl TextFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+
+        let bom = raw.starts_with(BOM);
+        let raw = raw.strip_prefix(BOM).unwrap_or(&raw);
+
+        // The first line ending decides the style of the whole file
+        let crlf = raw.find('\n').is_some_and(|index| raw[..index].ends_with('\r'));
+        let content = if crlf { raw.replace("\r\n", "\n") } else { raw.to_string() };
+
+        Ok(Self { content, bom, crlf })
+    }
+
+    /// Write `content` (with `\n` line endings) back in the file's original style
+    ///
+    /// The file is replaced atomically, so an interrupted run can't leave it truncated.
+    pub fn write(&self, path: &Path, content: &str) -> Result<()> {
+        let mut output = String::with_capacity(content.len() + content.len() / 20 + 3);
+        if self.bom {
+            output.push(BOM);
+        }
+        if self.crlf {
+            // Rendered sections may already carry CRLF, don't double the `\r`
+            output.push_str(&content.replace("\r\n", "\n").replace('\n', "\r\n"));
+        } else {
+            output.push_str(content);
+        }
+
+        write_atomic(path, output.as_bytes())
+            .with_context(|| format!("Failed to write file: {}", path.display()))
+    }
+}
+
+/// Write to a temporary file next to `path`, then rename it over `path`
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, process::id()));
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+
+        // Keep the permissions of the file being replaced
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Fresh file in the system temp dir, removed again on drop
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, bytes: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("text-{}-{}", process::id(), name));
+            fs::write(&path, bytes).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_bom_and_crlf() {
+        let original = "\u{feff}# Title\r\n<!--START_SECTION:waka-->\r\nold\r\n\
+                        <!--END_SECTION:waka-->\r\nend\r\n";
+        let file = TempFile::new("bom-crlf.md", original.as_bytes());
+
+        let text = TextFile::read(&file.0).unwrap();
+        assert_eq!(
+            text.content,
+            "# Title\n<!--START_SECTION:waka-->\nold\n<!--END_SECTION:waka-->\nend\n"
+        );
+
+        text.write(&file.0, &text.content.replace("old", "new")).unwrap();
+        assert_eq!(fs::read_to_string(&file.0).unwrap(), original.replace("old", "new"));
+    }
+
+    #[test]
+    fn unchanged_content_is_written_back_byte_identical() {
+        let original = "\u{feff}a\r\nb\r\n";
+        let file = TempFile::new("identical.md", original.as_bytes());
+
+        let text = TextFile::read(&file.0).unwrap();
+        text.write(&file.0, &text.content).unwrap();
+
+        assert_eq!(fs::read(&file.0).unwrap(), original.as_bytes());
+    }
+
+    #[test]
+    fn does_not_add_bom_or_crlf() {
+        let file = TempFile::new("plain.md", b"a\nb\n");
+
+        let text = TextFile::read(&file.0).unwrap();
+        text.write(&file.0, "a\nc\n").unwrap();
+
+        assert_eq!(fs::read(&file.0).unwrap(), b"a\nc\n");
+    }
+
+    #[test]
+    fn crlf_in_new_content_is_not_doubled() {
+        let file = TempFile::new("mixed.md", b"a\r\n");
+
+        let text = TextFile::read(&file.0).unwrap();
+        text.write(&file.0, "a\r\nb\n").unwrap();
+
+        assert_eq!(fs::read(&file.0).unwrap(), b"a\r\nb\r\n");
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_and_leaves_no_temp_file() {
+        let file = TempFile::new("atomic.md", b"old");
+
+        write_atomic(&file.0, b"new").unwrap();
+
+        assert_eq!(fs::read(&file.0).unwrap(), b"new");
+        let leftovers = fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!(".text-{}-atomic.md.", process::id()))
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
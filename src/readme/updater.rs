@@ -1,52 +1,287 @@
-use color_eyre::{eyre::{bail, WrapErr}, Result};
-use regex::Regex;
-use std::fs;
-use std::path::Path;
+use color_eyre::{eyre::{ContextCompat, WrapErr}, Result};
+use log::info;
+use similar::TextDiff;
+
+use super::change::ChangePolicy;
+use super::marker::{MarkerAnchor, MarkerStyle, TargetFile};
+use super::scanner::MarkerScanner;
+use super::text::TextFile;
 
 pub struct ReadmeUpdater;
 
 impl ReadmeUpdater {
-    pub fn update<P: AsRef<Path>>(path: P, section_name: &str, new_content: &str) -> Result<bool> {
-        let path = path.as_ref();
+    /// Replace every section (name, content) the file has markers for, returns whether it changed
+    ///
+    /// Markers of the sections in `insert` are added at its anchor first. Nothing is written
+    /// when no section changed significantly according to `policy`.
+    pub fn update(
+        target: &TargetFile,
+        sections: &[(String, String)],
+        policy: &ChangePolicy,
+        insert: Option<(&MarkerAnchor, &[String])>,
+    ) -> Result<bool> {
+        let path = &target.path;
+
+        let (file, content, new_readme) = Self::render(target, sections, insert)?;
+
+        // Check if content changed
+        if new_readme == file.content {
+            return Ok(false);
+        }
+        // New markers are always worth writing, whatever the policy says about the content
+        let inserted = content != file.content;
+        if !inserted && Self::significant(target, &content, sections, policy)?.is_empty() {
+            info!("Only volatile content changed in {}, skipping", path.display());
+            return Ok(false);
+        }
+
+        // Write file, keeping its line endings and BOM
+        file.write(path, &new_readme)?;
+
+        Ok(true)
+    }
+
+    /// Unified diff of what `update` would change, or `None` if nothing changes
+    pub fn preview(
+        target: &TargetFile,
+        sections: &[(String, String)],
+        policy: &ChangePolicy,
+        insert: Option<(&MarkerAnchor, &[String])>,
+    ) -> Result<Option<String>> {
+        let path = &target.path;
+
+        let (file, content, new_readme) = Self::render(target, sections, insert)?;
+
+        if new_readme == file.content {
+            return Ok(None);
+        }
+        let inserted = content != file.content;
+        if !inserted && Self::significant(target, &content, sections, policy)?.is_empty() {
+            info!("Only volatile content would change in {}", path.display());
+            return Ok(None);
+        }
+
+        let name = path.display().to_string();
+        let diff = TextDiff::from_lines(&file.content, &new_readme)
+            .unified_diff()
+            .header(&format!("a/{}", name), &format!("b/{}", name))
+            .to_string();
+
+        Ok(Some(diff))
+    }
+
+    /// Names of the sections whose content would change significantly
+    pub fn changed_sections(
+        target: &TargetFile,
+        sections: &[(String, String)],
+        policy: &ChangePolicy,
+        insert: Option<(&MarkerAnchor, &[String])>,
+    ) -> Result<Vec<String>> {
+        let (_, content, _) = Self::render(target, sections, insert)?;
+
+        Self::significant(target, &content, sections, policy)
+    }
+
+    /// `content` with empty start/end markers for the sections added at `anchor`
+    ///
+    /// The markers go in as one block, in the order of `section_names`.
+    fn with_markers(
+        target: &TargetFile,
+        content: &str,
+        section_names: &[String],
+        anchor: &MarkerAnchor,
+    ) -> Result<String> {
+        let path = &target.path;
+
+        if section_names.is_empty() {
+            return Ok(content.to_string());
+        }
+
+        let block = section_names
+            .iter()
+            .map(|section_name| {
+                format!(
+                    "{}\n{}\n",
+                    target.markers.start(section_name),
+                    target.markers.end(section_name)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
 
+        let new_content = match anchor {
+            MarkerAnchor::Top => format!("{}\n{}", block, content),
+            MarkerAnchor::End if content.is_empty() => block,
+            MarkerAnchor::End => {
+                let separator = if content.ends_with('\n') { "\n" } else { "\n\n" };
+                format!("{}{}{}", content, separator, block)
+            }
+            MarkerAnchor::AfterHeading(heading) => {
+                let offset = Self::heading_end(content, heading).with_context(|| {
+                    format!("Heading '{}' not found in {}", heading, path.display())
+                })?;
+                let (before, after) = content.split_at(offset);
+                let before = if before.ends_with('\n') {
+                    before.to_string()
+                } else {
+                    format!("{}\n", before)
+                };
+                format!("{}\n{}{}", before, block, after)
+            }
+        };
+
+        Ok(new_content)
+    }
+
+    /// Byte offset right after the heading line (and its RST underline, if any)
+    fn heading_end(content: &str, heading: &str) -> Option<usize> {
+        let strip = |line: &str| {
+            line.trim()
+                .trim_start_matches(['#', '=', '*'])
+                .trim_end_matches(['#', '='])
+                .trim()
+                .to_string()
+        };
+        let wanted = strip(heading);
+
+        let mut offset = 0;
+        let mut lines = content.split_inclusive('\n').peekable();
+        while let Some(line) = lines.next() {
+            offset += line.len();
+            if strip(line) != wanted || wanted.is_empty() {
+                continue;
+            }
+
+            // reStructuredText underlines the heading on the next line
+            if let Some(next) = lines.peek()
+                && is_underline(next)
+            {
+                offset += next.len();
+            }
+            return Some(offset);
+        }
+
+        None
+    }
+
+    /// Read the file and return (current file, content with the inserted markers, content with
+    /// all sections replaced)
+    fn render(
+        target: &TargetFile,
+        sections: &[(String, String)],
+        insert: Option<(&MarkerAnchor, &[String])>,
+    ) -> Result<(TextFile, String, String)> {
         // Read README
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        let file = TextFile::read(&target.path)?;
 
-        // Build regex pattern
-        let start_marker = format!("<!--START_SECTION:{}-->", section_name);
-        let end_marker = format!("<!--END_SECTION:{}-->", section_name);
+        let content = match insert {
+            Some((anchor, section_names)) => {
+                Self::with_markers(target, &file.content, section_names, anchor)?
+            }
+            None => file.content.clone(),
+        };
+        let new_readme = Self::replace_sections(target, &content, sections)?;
 
-        let pattern = format!(
-            r"(?s){}.*?{}",
-            regex::escape(&start_marker),
-            regex::escape(&end_marker)
-        );
+        Ok((file, content, new_readme))
+    }
 
-        let re = Regex::new(&pattern).context("Failed to create regex")?;
+    /// `content` with every section it has markers for replaced
+    fn replace_sections(
+        target: &TargetFile,
+        content: &str,
+        sections: &[(String, String)],
+    ) -> Result<String> {
+        let path = &target.path;
 
-        // Check if markers exist
-        if !re.is_match(&content) {
-            bail!(
-                "Markers {} and {} not found in {}",
-                start_marker,
-                end_marker,
-                path.display()
-            );
+        let mut new_readme = content.to_string();
+        let present = Self::present(target.markers, content, sections)
+            .with_context(|| format!("Invalid markers in {}", path.display()))?;
+        for (section_name, new_content) in present {
+            new_readme = Self::replace_section(&new_readme, target.markers, section_name, new_content)
+                .with_context(|| format!("Failed to update {}", path.display()))?;
         }
 
-        // Replace content
-        let replacement = format!("{}\n{}\n{}", start_marker, new_content, end_marker);
-        let new_readme = re.replace(&content, replacement.as_str()).to_string();
+        Ok(new_readme)
+    }
 
-        // Check if content changed
-        if new_readme == content {
-            return Ok(false);
+    /// Names of the sections whose new content differs from `content` according to `policy`
+    fn significant(
+        target: &TargetFile,
+        content: &str,
+        sections: &[(String, String)],
+        policy: &ChangePolicy,
+    ) -> Result<Vec<String>> {
+        let scanner = MarkerScanner::scan(content, target.markers);
+
+        let mut changed = Vec::new();
+        for (section_name, new_content) in sections {
+            let range = scanner
+                .section(section_name)
+                .with_context(|| format!("Invalid markers in {}", target.path.display()))?;
+            let Some(range) = range else {
+                continue;
+            };
+
+            // Same layout as `replace_section` writes
+            if policy.is_significant(&content[range], &format!("\n{}\n", new_content)) {
+                changed.push(section_name.clone());
+            }
         }
 
-        // Write file
-        fs::write(path, new_readme).with_context(|| format!("Failed to write file: {}", path.display()))?;
+        Ok(changed)
+    }
+
+    /// Sections the content has markers for, other files may hold the rest
+    fn present<'a>(
+        markers: MarkerStyle,
+        content: &str,
+        sections: &'a [(String, String)],
+    ) -> Result<Vec<&'a (String, String)>> {
+        let scanner = MarkerScanner::scan(content, markers);
 
-        Ok(true)
+        let mut present = Vec::new();
+        for section in sections {
+            if scanner.section(&section.0)?.is_some() {
+                present.push(section);
+            }
+        }
+
+        Ok(present)
+    }
+
+    fn replace_section(
+        content: &str,
+        markers: MarkerStyle,
+        section_name: &str,
+        new_content: &str,
+    ) -> Result<String> {
+        let range = MarkerScanner::scan(content, markers)
+            .section(section_name)?
+            .with_context(|| {
+                format!(
+                    "Markers {} and {} not found",
+                    markers.start(section_name),
+                    markers.end(section_name)
+                )
+            })?;
+
+        // Replace everything between the markers, the markers keep their spelling
+        Ok(format!(
+            "{}\n{}\n{}",
+            &content[..range.start],
+            new_content,
+            &content[range.end..]
+        ))
+    }
+}
+
+fn is_underline(line: &str) -> bool {
+    let line = line.trim();
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(first) if "=-~^\"'`#*+".contains(first) => {
+            line.len() >= 2 && chars.all(|c| c == first)
+        }
+        _ => false,
     }
 }
@@ -1,27 +1,56 @@
 use color_eyre::{eyre::{bail, WrapErr}, Result};
-use std::fs;
-use std::path::Path;
+
+use super::marker::TargetFile;
+use super::scanner::MarkerScanner;
+use super::text::TextFile;
 
 pub struct ReadmeValidator;
 
 impl ReadmeValidator {
-    pub fn ensure_section_exists<P: AsRef<Path>>(path: P, section_name: &str) -> Result<()> {
-        let path = path.as_ref();
+    /// Whether at least one of the files contains the section's markers
+    pub fn has_section(targets: &[TargetFile], section_name: &str) -> Result<bool> {
+        for target in targets {
+            let content = TextFile::read(&target.path)?.content;
+
+            let section = MarkerScanner::scan(&content, target.markers)
+                .section(section_name)
+                .with_context(|| format!("Invalid markers in {}", target.path.display()))?;
 
-        let content = fs::read_to_string(path)
-            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            if section.is_some() {
+                return Ok(true);
+            }
+        }
 
-        let start_marker = format!("<!--START_SECTION:{}-->", section_name);
-        let end_marker = format!("<!--END_SECTION:{}-->", section_name);
+        Ok(false)
+    }
 
-        if content.contains(&start_marker) && content.contains(&end_marker) {
+    /// Make sure at least one of the files contains the section's markers
+    pub fn ensure_section_exists(targets: &[TargetFile], section_name: &str) -> Result<()> {
+        if Self::has_section(targets, section_name)? {
             return Ok(());
         }
 
+        if let [target] = targets {
+            bail!(
+                "Required markers are missing in README. Please add:\n{}\n{}\n",
+                target.markers.start(section_name),
+                target.markers.end(section_name)
+            )
+        }
+
+        let mut expected = String::new();
+        for target in targets {
+            expected.push_str(&format!(
+                "{}:\n{}\n{}\n",
+                target.path.display(),
+                target.markers.start(section_name),
+                target.markers.end(section_name)
+            ));
+        }
+
         bail!(
-            "Required markers are missing in README. Please add:\n{}\n{}\n",
-            start_marker,
-            end_marker
+            "Required markers are missing. Please add them to one of these files:\n{}",
+            expected
         )
     }
 }
@@ -0,0 +1,19 @@
+use color_eyre::Result;
+
+use crate::wakatime::client::WakaTimeClient;
+use super::types::{AllTimeData, AllTimeResponse};
+
+impl WakaTimeClient {
+    /// Get the total coding time since the account was created
+    ///
+    /// Keeps polling while WakaTime is still calculating the total.
+    pub async fn get_all_time_since_today(&self) -> Result<AllTimeData> {
+        let response: AllTimeResponse = self
+            .fetch("/users/current/all_time_since_today", |response: &AllTimeResponse| {
+                response.data.is_up_to_date.unwrap_or(true)
+            })
+            .await?;
+
+        Ok(response.data)
+    }
+}
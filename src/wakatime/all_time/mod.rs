@@ -0,0 +1,5 @@
+mod api;
+mod types;
+
+// Re-export types
+pub use types::AllTimeData;
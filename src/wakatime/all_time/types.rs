@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct AllTimeResponse {
+    pub data: AllTimeData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllTimeData {
+    pub total_seconds: f64,
+    #[serde(default)]
+    pub text: String,
+    pub range: Option<AllTimeRange>,
+    /// False while WakaTime is still calculating the total (missing on other backends)
+    pub is_up_to_date: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AllTimeRange {
+    /// First day with coding activity, as `YYYY-MM-DD`
+    pub start_date: Option<String>,
+}
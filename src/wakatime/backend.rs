@@ -0,0 +1,24 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// WakaTime-compatible server implementation the stats are fetched from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    #[value(name = "wakatime")]
+    WakaTime,
+    Wakapi,
+    Hakatime,
+}
+
+impl Backend {
+    /// API base URL used when none is configured (Hakatime is always self-hosted)
+    pub fn default_base_url(self) -> Option<&'static str> {
+        match self {
+            Backend::WakaTime => Some("https://wakatime.com/api/v1"),
+            Backend::Wakapi => Some("https://wakapi.dev/api/compat/wakatime/v1"),
+            Backend::Hakatime => None,
+        }
+    }
+}
@@ -1,15 +1,192 @@
-use reqwest::Client;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::{eyre::{bail, eyre, WrapErr}, Report, Result};
+use log::warn;
+use reqwest::{header::RETRY_AFTER, Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Upper bound for a single backoff delay
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// How often and how patiently requests are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every further retry
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff for the given retry (1-based), capped and with up to 25% jitter
+    fn delay(&self, retry: u32) -> Duration {
+        let base = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(MAX_RETRY_DELAY);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let jitter = base.mul_f64(f64::from(nanos % 1000) / 4000.0);
+
+        base + jitter
+    }
+}
+
+/// Result of a single request attempt
+enum Attempt<T> {
+    Done(T),
+    Retry(Report, Option<Duration>),
+}
 
 pub struct WakaTimeClient {
     pub(crate) client: Client,
     pub(crate) api_key: String,
+    pub(crate) base_url: String,
+    pub(crate) retry: RetryPolicy,
 }
 
 impl WakaTimeClient {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, base_url: String, retry: RetryPolicy) -> Self {
         Self {
             client: Client::new(),
             api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            retry,
+        }
+    }
+
+    /// Build a GET request for an API path, authenticated via `Authorization: Basic`
+    ///
+    /// The key never goes into the URL, so it cannot end up in request errors.
+    pub(crate) fn get(&self, path: &str) -> RequestBuilder {
+        self.client
+            .get(format!("{}{}", self.base_url, path))
+            .header("Authorization", format!("Basic {}", STANDARD.encode(&self.api_key)))
+    }
+
+    /// GET and deserialize an API path, retrying according to the retry policy
+    ///
+    /// Network errors, `202 Accepted` (still calculating), `429` and `5xx` are retried.
+    /// A parsed response is retried as well while `is_complete` returns false.
+    pub(crate) async fn fetch<T, F>(&self, path: &str, is_complete: F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> bool,
+    {
+        let (value, _) = self.fetch_with_body(path, is_complete).await?;
+        Ok(value)
+    }
+
+    /// Like `fetch`, but also returns the response body as received
+    pub(crate) async fn fetch_with_body<T, F>(&self, path: &str, is_complete: F) -> Result<(T, String)>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> bool,
+    {
+        let max_attempts = self.retry.max_attempts.max(1);
+        let mut attempt = 1;
+
+        loop {
+            let (error, retry_after) = match self.attempt(path, &is_complete).await? {
+                Attempt::Done(value) => return Ok(value),
+                Attempt::Retry(error, retry_after) => (error, retry_after),
+            };
+
+            if attempt >= max_attempts {
+                return Err(error.wrap_err(format!("Giving up after {} attempts", attempt)));
+            }
+
+            let delay = retry_after.unwrap_or_else(|| self.retry.delay(attempt));
+            warn!(
+                "{} (attempt {}/{}), retrying in {:.1}s",
+                error,
+                attempt,
+                max_attempts,
+                delay.as_secs_f64()
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn attempt<T, F>(&self, path: &str, is_complete: &F) -> Result<Attempt<(T, String)>>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> bool,
+    {
+        let response = match self.get(path).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                let error = Report::new(e.without_url()).wrap_err("Failed to request WakaTime API");
+                return Ok(Attempt::Retry(error, None));
+            }
+        };
+
+        let status = response.status();
+
+        if status == StatusCode::ACCEPTED {
+            return Ok(Attempt::Retry(eyre!("WakaTime is still calculating stats"), None));
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            let retry_after = retry_after(&response);
+            let text = response.text().await.unwrap_or_default();
+            let error = eyre!("WakaTime API returned error {}: {}", status, self.redact(&text));
+            return Ok(Attempt::Retry(error, retry_after));
+        }
+
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            bail!("WakaTime API returned error {}: {}", status, self.redact(&text));
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| e.without_url())
+            .context("Failed to read WakaTime API response")?;
+        let value: T =
+            serde_json::from_str(&body).context("Failed to parse WakaTime API response")?;
+
+        if !is_complete(&value) {
+            return Ok(Attempt::Retry(eyre!("WakaTime stats are not up to date yet"), None));
         }
+
+        Ok(Attempt::Done((value, body)))
     }
+
+    /// Strip the API key (plain and base64 encoded) from text that may end up in logs
+    pub(crate) fn redact(&self, text: &str) -> String {
+        if self.api_key.is_empty() {
+            return text.to_string();
+        }
+
+        text.replace(&self.api_key, "***")
+            .replace(&STANDARD.encode(&self.api_key), "***")
+    }
+}
+
+/// Delay requested by the server via a `Retry-After: <seconds>` header
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(|secs: u64| Duration::from_secs(secs).min(MAX_RETRY_DELAY))
 }
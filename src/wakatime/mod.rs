@@ -1,5 +1,9 @@
+pub mod all_time;
+mod backend;
 mod client;
 pub mod stats;
+pub mod summaries;
 
 // Re-export public API
-pub use client::WakaTimeClient;
+pub use backend::Backend;
+pub use client::{RetryPolicy, WakaTimeClient};
@@ -1,39 +1,25 @@
-use color_eyre::{eyre::{bail, WrapErr}, Result};
+use color_eyre::Result;
 
 use crate::wakatime::client::WakaTimeClient;
 use super::types::{StatsData, StatsResponse};
 
-const WAKATIME_API_BASE: &str = "https://wakatime.com/api/v1";
-
 impl WakaTimeClient {
-    /// Get coding stats for a specific time range
+    /// Get coding stats for a specific time range, along with the response body as received
+    ///
+    /// Keeps polling while WakaTime is still calculating the range.
     ///
     /// # Arguments
     /// * `range` - Time range (e.g., "last_7_days", "last_30_days")
-    pub async fn get_stats(&self, range: &str) -> Result<StatsData> {
-        let url = format!(
-            "{}/users/current/stats/{}?api_key={}",
-            WAKATIME_API_BASE, range, self.api_key
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .context("Failed to request WakaTime API")?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            bail!("WakaTime API returned error {}: {}", status, text);
-        }
+    pub async fn get_stats(&self, range: &str) -> Result<(StatsData, String)> {
+        let (stats_response, body): (StatsResponse, String) = self
+            .fetch_with_body(&format!("/users/current/stats/{}", range), |response: &StatsResponse| {
+                response.data.is_up_to_date.unwrap_or(true)
+            })
+            .await?;
 
-        let stats_response: StatsResponse = response
-            .json()
-            .await
-            .context("Failed to parse WakaTime API response")?;
+        let mut stats = stats_response.data;
+        stats.fill_missing();
 
-        Ok(stats_response.data)
+        Ok((stats, body))
     }
 }
@@ -0,0 +1,33 @@
+use color_eyre::{eyre::WrapErr, Result};
+use std::fs;
+use std::path::Path;
+
+use super::types::{StatsData, StatsResponse};
+
+impl StatsData {
+    /// Load stats from a saved `/users/current/stats/{range}` response, along with its content
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<(Self, String)> {
+        let path = path.as_ref();
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read stats file: {}", path.display()))?;
+
+        let stats_response: StatsResponse = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse stats file: {}", path.display()))?;
+
+        let mut stats = stats_response.data;
+        stats.fill_missing();
+
+        Ok((stats, content))
+    }
+
+    /// Save a stats response body unchanged, so `load` sees exactly what the API returned
+    pub fn save<P: AsRef<Path>>(body: &str, path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        fs::write(path, body)
+            .with_context(|| format!("Failed to write stats file: {}", path.display()))?;
+
+        Ok(())
+    }
+}
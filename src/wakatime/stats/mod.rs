@@ -1,5 +1,6 @@
 mod api;
+mod file;
 mod types;
 
 // Re-export types
-pub use types::StatsData;
+pub use types::{StatItem, StatsData};
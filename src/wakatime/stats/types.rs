@@ -1,24 +1,164 @@
-use serde::Deserialize;
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::formatter::format_duration;
 
 #[derive(Debug, Deserialize)]
 pub struct StatsResponse {
     pub data: StatsData,
 }
 
-#[derive(Debug, Deserialize)]
+// Wakapi and Hakatime omit some fields that WakaTime always sends, so
+// everything except the item names is optional and filled in afterwards.
+#[derive(Debug, Deserialize, Serialize)]
 pub struct StatsData {
+    #[serde(default)]
     pub languages: Vec<StatItem>,
+    #[serde(default)]
     pub editors: Vec<StatItem>,
+    #[serde(default)]
     pub operating_systems: Vec<StatItem>,
+    #[serde(default)]
     pub projects: Vec<StatItem>,
+    /// Coding, building, debugging, ...
+    #[serde(default)]
+    pub categories: Vec<StatItem>,
+    #[serde(default)]
+    pub machines: Vec<StatItem>,
+    /// Libraries used in the code, when the plugin reports them
+    #[serde(default)]
+    pub dependencies: Vec<StatItem>,
+    #[serde(default)]
     pub total_seconds: f64,
+    pub total_seconds_including_other_language: Option<f64>,
+    pub human_readable_total: Option<String>,
+    pub human_readable_total_including_other_language: Option<String>,
+    /// Average coding time per day in seconds, without holidays
+    pub daily_average: Option<f64>,
+    pub daily_average_including_other_language: Option<f64>,
+    pub human_readable_daily_average: Option<String>,
+    pub human_readable_daily_average_including_other_language: Option<String>,
+    /// Day with the most coding time in the range
+    pub best_day: Option<BestDay>,
+    /// Range name, e.g. `last_7_days`
+    pub range: Option<String>,
+    /// Range description, e.g. `last 7 days`
+    pub human_readable_range: Option<String>,
+    /// Days without any coding activity
+    pub holidays: Option<u32>,
+    pub days_including_holidays: Option<u32>,
+    pub days_minus_holidays: Option<u32>,
+    /// Calculation status, e.g. `ok` or `pending_update`
+    pub status: Option<String>,
+    /// How far WakaTime got calculating the range (0-100)
+    pub percent_calculated: Option<f64>,
+    pub is_already_updating: Option<bool>,
+    pub is_coding_activity_visible: Option<bool>,
+    pub is_other_usage_visible: Option<bool>,
+    pub is_stuck: Option<bool>,
+    pub is_including_today: Option<bool>,
+    /// False while WakaTime is still calculating the range (missing on other backends)
+    pub is_up_to_date: Option<bool>,
+    /// Start of the range as an ISO 8601 datetime
+    pub start: Option<String>,
+    /// End of the range as an ISO 8601 datetime
+    pub end: Option<String>,
+    /// The user's timezone, e.g. `Europe/Berlin`
+    pub timezone: Option<String>,
+    /// Minutes of inactivity after which heartbeats are no longer joined
+    pub timeout: Option<u32>,
+    pub writes_only: Option<bool>,
+    pub user_id: Option<String>,
+    pub username: Option<String>,
+    pub created_at: Option<String>,
+    pub modified_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct StatItem {
     pub name: String,
+    #[serde(default)]
     pub percent: f64,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub total_seconds: f64,
+    #[serde(default)]
+    pub hours: u64,
+    #[serde(default)]
+    pub minutes: u64,
+    /// Time as `H:MM`
+    #[serde(default)]
+    pub digital: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BestDay {
+    /// Missing when the range has no activity, some backends then send `""` or `null`
+    #[serde(default, deserialize_with = "lenient_date")]
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
     pub text: String,
-    #[allow(dead_code)]
+    #[serde(default)]
     pub total_seconds: f64,
 }
+
+/// `YYYY-MM-DD`, anything else (empty, `null`, other formats) is treated as no date
+fn lenient_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()))
+}
+
+impl StatsData {
+    /// First and last day of the range in `timezone`, if the backend sent both ends
+    pub fn date_range(&self, timezone: Tz) -> Option<(NaiveDate, NaiveDate)> {
+        let date = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|datetime| datetime.with_timezone(&timezone).date_naive())
+        };
+
+        Some((date(self.start.as_deref()?)?, date(self.end.as_deref()?)?))
+    }
+
+    /// Derive totals, percentages and texts the backend did not send
+    pub(crate) fn fill_missing(&mut self) {
+        if self.total_seconds <= 0.0 {
+            self.total_seconds = self.languages.iter().map(|item| item.total_seconds).sum();
+        }
+
+        if self.human_readable_total.is_none() {
+            self.human_readable_total = Some(format_duration(self.total_seconds));
+        }
+
+        let total_seconds = self.total_seconds;
+        for items in [
+            &mut self.languages,
+            &mut self.editors,
+            &mut self.operating_systems,
+            &mut self.projects,
+            &mut self.categories,
+            &mut self.machines,
+            &mut self.dependencies,
+        ] {
+            for item in items.iter_mut() {
+                if item.percent <= 0.0 && total_seconds > 0.0 {
+                    item.percent = item.total_seconds / total_seconds * 100.0;
+                }
+                if item.text.is_empty() {
+                    item.text = format_duration(item.total_seconds);
+                }
+                if item.digital.is_empty() {
+                    let minutes = (item.total_seconds / 60.0).floor() as u64;
+                    item.hours = minutes / 60;
+                    item.minutes = minutes % 60;
+                    item.digital = format!("{}:{:02}", item.hours, item.minutes);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDate;
+use color_eyre::Result;
+
+use crate::wakatime::client::WakaTimeClient;
+use super::types::{DaySummary, SummariesResponse};
+
+impl WakaTimeClient {
+    /// Get per-day coding activity between two dates (both inclusive)
+    pub async fn get_summaries(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<DaySummary>> {
+        let path = format!(
+            "/users/current/summaries?start={}&end={}",
+            start.format("%Y-%m-%d"),
+            end.format("%Y-%m-%d")
+        );
+
+        let response: SummariesResponse = self.fetch(&path, |_: &SummariesResponse| true).await?;
+
+        Ok(response.data)
+    }
+}
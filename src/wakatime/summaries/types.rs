@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct SummariesResponse {
+    pub data: Vec<DaySummary>,
+}
+
+/// Coding activity of a single day
+#[derive(Debug, Deserialize)]
+pub struct DaySummary {
+    pub grand_total: GrandTotal,
+    pub range: DayRange,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GrandTotal {
+    pub total_seconds: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DayRange {
+    pub date: NaiveDate,
+}